@@ -0,0 +1,113 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use mdbook::errors::Error;
+
+/// Encrypts the archive of removed content before it is written
+///
+/// Implementations can wrap tools such as age or gpg, encrypting to a recipient of their choice.
+pub trait Encryptor {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// Content stripped from the book by a `remove` build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArchiveEntry {
+    /// Source path of the chapter the content was taken from
+    pub(crate) path: String,
+    /// Whether the whole chapter was removed, rather than a block within it
+    pub(crate) chapter: bool,
+    pub(crate) content: String,
+}
+
+/// Describe the removed content in markdown, one section per chapter or block
+pub(crate) fn render(entries: &[ArchiveEntry]) -> String {
+    let mut archive = String::from("# mdbook-private archive\n");
+    for entry in entries {
+        let kind = if entry.chapter { "chapter" } else { "block" };
+        let _ = write!(
+            archive,
+            "\n## {} ({})\n\n{}\n",
+            entry.path,
+            kind,
+            entry.content.trim_end()
+        );
+    }
+    archive
+}
+
+/// Write the archive, encrypted when an encryptor is given
+pub(crate) fn write(
+    path: &Path,
+    entries: &[ArchiveEntry],
+    encryptor: Option<&dyn Encryptor>,
+) -> Result<(), Error> {
+    let plaintext = render(entries);
+    let bytes = match encryptor {
+        Some(encryptor) => encryptor.encrypt(plaintext.as_bytes())?,
+        None => plaintext.into_bytes(),
+    };
+
+    fs::write(path, bytes).map_err(|e| {
+        Error::new(e).context(format!("Unable to write archive to {}", path.display()))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    fn entries() -> Vec<ArchiveEntry> {
+        vec![
+            ArchiveEntry {
+                path: "chapter_1.md".to_string(),
+                chapter: false,
+                content: "Launch date: March 3rd\n".to_string(),
+            },
+            ArchiveEntry {
+                path: "_chapter_2.md".to_string(),
+                chapter: true,
+                content: "# Chapter 2\n\nInternal roadmap\n".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn plaintext_archive_contents() {
+        assert_eq!(
+            render(&entries()),
+            "# mdbook-private archive\n\
+             \n## chapter_1.md (block)\n\nLaunch date: March 3rd\n\
+             \n## _chapter_2.md (chapter)\n\n# Chapter 2\n\nInternal roadmap\n"
+        );
+    }
+
+    struct Reverse {
+        calls: Cell<usize>,
+    }
+
+    impl Encryptor for Reverse {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(plaintext.iter().rev().copied().collect())
+        }
+    }
+
+    #[test]
+    fn encryptor_is_invoked() {
+        let path = std::env::temp_dir().join(format!("mdbook-private-{}.md", std::process::id()));
+        let encryptor = Reverse {
+            calls: Cell::new(0),
+        };
+
+        write(&path, &entries(), Some(&encryptor)).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(encryptor.calls.get(), 1);
+        let expected: Vec<u8> = render(&entries()).into_bytes().into_iter().rev().collect();
+        assert_eq!(written, expected);
+    }
+}
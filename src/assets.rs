@@ -0,0 +1,157 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::pipeline;
+
+static REFERENCE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[[^\]]*\]\(\s*<?([^)\s>]+)").unwrap());
+
+static SRC_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\bsrc\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+static INLINE_REFERENCE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^!?\[[^\]\n]*\]\([^)\n]*\)$").unwrap());
+
+static LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"(?P<code>``(?s:.*?)``|`[^`]*`)|"#,
+        r#"(?P<bang>!?)\[(?P<text>(?:<!--(?s:.*?)-->|[^\[\]])*)\]"#,
+        r#"\((?P<target>\s*[^)\s]*)(?:(?P<space>\s+)"(?P<title>[^"]*)")?\s*\)"#
+    ))
+    .unwrap()
+});
+
+/// Rewrite the alt text or text and the title of every image and link
+///
+/// Code blocks and code spans are left as written.
+pub(crate) fn map_link_text(markdown: &str, map: impl Fn(&str) -> String) -> String {
+    pipeline::map_prose(markdown, |prose| {
+        LINK_RE
+            .replace_all(prose, |caps: &regex::Captures| link_text(caps, &map))
+            .into_owned()
+    })
+}
+
+/// The matched image or link with its text and title mapped, or the code span as written
+fn link_text(caps: &regex::Captures, map: impl Fn(&str) -> String) -> String {
+    if let Some(code) = caps.name("code") {
+        return code.as_str().to_string();
+    }
+    let title = match (caps.name("space"), caps.name("title")) {
+        (Some(space), Some(title)) => format!("{}\"{}\"", space.as_str(), map(title.as_str())),
+        _ => String::new(),
+    };
+    format!(
+        "{}[{}]({}{})",
+        &caps["bang"],
+        map(&caps["text"]),
+        &caps["target"],
+        title
+    )
+}
+
+/// Local files referenced by the images, links and raw `src` attributes in a piece of markdown
+pub(crate) fn references(markdown: &str) -> Vec<String> {
+    let links = REFERENCE_RE
+        .captures_iter(markdown)
+        .map(|caps| caps[1].to_string());
+    let sources = SRC_RE
+        .captures_iter(markdown)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)))
+        .map(|target| target.as_str().to_string());
+
+    links
+        .chain(sources)
+        .filter(|target| !target.is_empty() && is_local(target))
+        .collect()
+}
+
+/// Path of a referenced file relative to the book's source directory
+///
+/// Targets are relative to the chapter referencing them, so `../img/a.png` in `guide/intro.md`
+/// and `img/a.png` in `summary.md` both resolve to `img/a.png`.
+pub(crate) fn resolve(chapter: &Path, target: &str) -> PathBuf {
+    let target = target.split(['#', '?']).next().unwrap_or_default();
+    let base = chapter.parent().unwrap_or(Path::new(""));
+
+    let mut resolved = PathBuf::new();
+    for component in base.join(target).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(part) => resolved.push(part),
+            _ => {}
+        }
+    }
+    resolved
+}
+
+/// Whether the content is nothing but a single image or link
+pub(crate) fn is_inline_reference(markdown: &str) -> bool {
+    INLINE_REFERENCE_RE.is_match(markdown.trim())
+}
+
+fn is_local(target: &str) -> bool {
+    !(target.contains("://") || target.starts_with('#') || target.starts_with("mailto:"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn local_references() {
+        let markdown = "![diagram](img/internal.png) and [spec](spec.pdf \"Spec\"), \
+                        [site](https://example.com) [top](#top)";
+        assert_eq!(references(markdown), vec!["img/internal.png", "spec.pdf"]);
+    }
+
+    #[test]
+    fn raw_src_references() {
+        let markdown = "<img src=\"img/raw.png\"> <video src='clip.mp4'></video> \
+                        <img src=\"https://example.com/a.png\">";
+        assert_eq!(references(markdown), vec!["img/raw.png", "clip.mp4"]);
+    }
+
+    #[test]
+    fn resolve_relative_to_chapter() {
+        assert_eq!(
+            resolve(Path::new("guide/intro.md"), "../img/a.png#top"),
+            PathBuf::from("img/a.png")
+        );
+        assert_eq!(
+            resolve(Path::new("summary.md"), "./img/a.png"),
+            PathBuf::from("img/a.png")
+        );
+    }
+
+    #[test]
+    fn map_alt_text_and_title() {
+        let markdown = "![alt](a.png \"title\") [text](b.md) [plain] ![x](c.png)";
+        assert_eq!(
+            map_link_text(markdown, |text| text.to_uppercase()),
+            "![ALT](a.png \"TITLE\") [TEXT](b.md) [plain] ![X](c.png)"
+        );
+    }
+
+    #[test]
+    fn map_link_text_skips_code() {
+        let markdown = "```md\n[a <!--private b-->](c.md)\n```\n\n    [d](e.md)\n\n\
+                        `[f](g.md)` [h](i.md)\n";
+        assert_eq!(
+            map_link_text(markdown, |text| text.to_uppercase()),
+            "```md\n[a <!--private b-->](c.md)\n```\n\n    [d](e.md)\n\n\
+             `[f](g.md)` [H](i.md)\n"
+        );
+    }
+
+    #[test]
+    fn inline_reference() {
+        assert!(is_inline_reference(" ![diagram](internal.png) "));
+        assert!(is_inline_reference("[spec](spec.pdf)"));
+        assert!(!is_inline_reference("See ![diagram](internal.png)"));
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use mdbook::errors::Error;
+
+/// The `key="value"` attributes leading the content of a private block
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Attributes<'a> {
+    values: HashMap<&'a str, &'a str>,
+    /// Content following the attributes
+    pub(crate) body: &'a str,
+}
+
+impl<'a> Attributes<'a> {
+    /// A block without attributes
+    pub(crate) fn none(body: &'a str) -> Attributes<'a> {
+        Attributes {
+            values: HashMap::new(),
+            body,
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&'a str> {
+        self.values.get(key).copied()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Keys of the attributes a block can be given
+const KEYS: [&str; 4] = ["label", "langs", "raw", "reason"];
+
+/// Keys that may be given without a value, standing for `key="true"`
+const FLAGS: [&str; 1] = ["raw"];
+
+/// Parse the attributes at the start of a private block's content
+///
+/// Attributes come in any order, as a lowercase key, an `=` that may be surrounded by spaces, and
+/// a value in single or double quotes. They end at the first text that isn't an attribute, which
+/// is the body of the block. A quote that is never closed is an error.
+///
+/// Only the keys in `KEYS` are attributes. A line holding any other `key="value"` pair, such as
+/// `host="db1"`, is content as a whole, so the attributes end before it.
+///
+/// A flag such as `raw` may also be given on its own, when it ends the line or is followed by
+/// another attribute or by HTML, so that a body starting with the word is left alone.
+pub(crate) fn parse_marker_attributes(content: &str) -> Result<Attributes<'_>, Error> {
+    let mut attributes = Attributes::none(content);
+    let mut rest = content;
+    // Start of the current line, and the values its attributes replaced, to give the line back
+    let mut line_start = content;
+    let mut line_values = Vec::new();
+
+    while let Some((key, after_key)) = split_key(rest) {
+        let Some(after_eq) = after_key
+            .trim_start_matches([' ', '\t'])
+            .strip_prefix('=')
+            .map(|value| value.trim_start_matches([' ', '\t']))
+        else {
+            if FLAGS.contains(&key) && ends_flag(after_key) {
+                line_values.push((key, attributes.values.insert(key, "true")));
+                rest = after_key.trim_start();
+                if after_key[..after_key.len() - rest.len()].contains('\n') {
+                    line_start = rest;
+                    line_values.clear();
+                }
+                continue;
+            }
+            break;
+        };
+        if !KEYS.contains(&key) {
+            for (key, previous) in line_values.into_iter().rev() {
+                match previous {
+                    Some(value) => attributes.values.insert(key, value),
+                    None => attributes.values.remove(key),
+                };
+            }
+            rest = line_start;
+            break;
+        }
+        let Some(quote) = after_eq.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+
+        let quoted = &after_eq[1..];
+        let end = quoted.find(quote).ok_or_else(|| {
+            Error::msg(format!(
+                "Unterminated value of the `{}` attribute, missing a closing {}",
+                key, quote
+            ))
+        })?;
+        line_values.push((key, attributes.values.insert(key, &quoted[..end])));
+        let after = &quoted[end + 1..];
+        rest = after.trim_start();
+        if after[..after.len() - rest.len()].contains('\n') {
+            line_start = rest;
+            line_values.clear();
+        }
+    }
+
+    attributes.body = rest;
+    Ok(attributes)
+}
+
+/// Whether the text after a bare key makes it a flag rather than the first word of the body
+fn ends_flag(after_key: &str) -> bool {
+    let next = after_key.trim_start_matches([' ', '\t']);
+    if next.is_empty() || next.starts_with(['\r', '\n']) {
+        return true;
+    }
+    let spaced = next.len() < after_key.len();
+    let attribute = split_key(next)
+        .is_some_and(|(_, after)| after.trim_start_matches([' ', '\t']).starts_with('='));
+    spaced && (next.starts_with('<') || attribute)
+}
+
+/// Split a leading `[a-z][a-z-]*` key off the content
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    if !content.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return None;
+    }
+    let end = content
+        .find(|c: char| !c.is_ascii_lowercase() && c != '-')
+        .unwrap_or(content.len());
+    Some(content.split_at(end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn attributes_in_any_order_and_quotes() {
+        let attributes =
+            parse_marker_attributes("reason='Under NDA' langs=\"en fr\"\nLaunch date").unwrap();
+        assert_eq!(attributes.get("reason"), Some("Under NDA"));
+        assert_eq!(attributes.get("langs"), Some("en fr"));
+        assert_eq!(attributes.body, "Launch date");
+
+        let swapped =
+            parse_marker_attributes("langs=\"en fr\" reason='Under NDA'\nLaunch date").unwrap();
+        assert_eq!(swapped, attributes);
+    }
+
+    #[test]
+    fn attributes_with_surrounding_whitespace() {
+        let attributes =
+            parse_marker_attributes("label = \"internal\"\t reason\t=\t'It\"s secret'  \n\nBody")
+                .unwrap();
+        assert_eq!(attributes.get("label"), Some("internal"));
+        assert_eq!(attributes.get("reason"), Some("It\"s secret"));
+        assert_eq!(attributes.body, "Body");
+    }
+
+    #[test]
+    fn empty_values() {
+        let attributes = parse_marker_attributes("reason=\"\" label=''Body").unwrap();
+        assert_eq!(attributes.get("reason"), Some(""));
+        assert_eq!(attributes.get("label"), Some(""));
+        assert_eq!(attributes.body, "Body");
+    }
+
+    #[test]
+    fn body_without_attributes() {
+        for content in [
+            "Launch date: March 3rd",
+            "note = important",
+            "Reason=\"capitalized\"",
+            "",
+        ] {
+            assert_eq!(
+                parse_marker_attributes(content).unwrap(),
+                Attributes::none(content)
+            );
+        }
+    }
+
+    #[test]
+    fn bare_flags() {
+        for content in ["raw\n<table>", "raw <table>", "raw label=\"x\"\n<table>"] {
+            let attributes = parse_marker_attributes(content).unwrap();
+            assert_eq!(attributes.get("raw"), Some("true"), "{}", content);
+            assert_eq!(attributes.body, "<table>");
+        }
+        let attributes = parse_marker_attributes("label='x' raw\n<table>").unwrap();
+        assert_eq!(attributes.get("raw"), Some("true"));
+        assert_eq!(attributes.get("label"), Some("x"));
+
+        // A body starting with the word keeps it
+        for content in ["raw data follows", "raw<b>", "rawness", "label x"] {
+            assert_eq!(
+                parse_marker_attributes(content).unwrap(),
+                Attributes::none(content)
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_keys_stay_in_body() {
+        let content = "host=\"db1\" port=\"5432\"\nafter";
+        assert_eq!(
+            parse_marker_attributes(content).unwrap(),
+            Attributes::none(content)
+        );
+
+        // The whole line holding the unknown key is content
+        let attributes =
+            parse_marker_attributes("reason='NDA'\nlabel='x' host=\"db1\"\nafter").unwrap();
+        assert_eq!(attributes.get("reason"), Some("NDA"));
+        assert_eq!(attributes.get("label"), None);
+        assert_eq!(attributes.body, "label='x' host=\"db1\"\nafter");
+
+        let attributes = parse_marker_attributes("raw host=\"db1\"\n<table>").unwrap();
+        assert!(attributes.is_empty());
+        assert_eq!(attributes.body, "raw host=\"db1\"\n<table>");
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        let err = parse_marker_attributes("label=\"internal reason='x'\nBody").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unterminated value of the `label` attribute, missing a closing \""
+        );
+        assert!(parse_marker_attributes("reason='Under NDA").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Config, Encryptor, Private, StyleVariant};
+
+/// Configure a [`Private`] in code, for books built through the mdbook library
+///
+/// Options set in the book's table for the preprocessor are applied over this configuration,
+/// as they are for [`Private::with_config`], and the others are kept.
+///
+/// ```
+/// use mdbook_private::PrivateBuilder;
+///
+/// let private = PrivateBuilder::new()
+///     .remove(true)
+///     .notice("INTERNAL")
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct PrivateBuilder {
+    name: Option<String>,
+    config: Config,
+    levels: Option<Vec<String>>,
+    encryptor: Option<Box<dyn Encryptor>>,
+}
+
+impl PrivateBuilder {
+    pub fn new() -> PrivateBuilder {
+        PrivateBuilder::default()
+    }
+
+    /// Start from an existing configuration
+    pub fn config(mut self, config: Config) -> PrivateBuilder {
+        self.config = config;
+        self
+    }
+
+    /// Read `[preprocessor.<name>]` instead of `[preprocessor.private]`
+    pub fn name(mut self, name: &str) -> PrivateBuilder {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn remove(mut self, remove: bool) -> PrivateBuilder {
+        self.config.remove = remove;
+        self
+    }
+
+    pub fn style(mut self, style: bool) -> PrivateBuilder {
+        self.config.style = style;
+        self
+    }
+
+    pub fn style_variant(mut self, variant: StyleVariant) -> PrivateBuilder {
+        self.config.style_variant = variant;
+        self
+    }
+
+    pub fn notice(mut self, notice: &str) -> PrivateBuilder {
+        self.config.notice = notice.to_string();
+        self
+    }
+
+    pub fn css_class(mut self, css_class: bool) -> PrivateBuilder {
+        self.config.css_class = css_class;
+        self
+    }
+
+    pub fn chapter_prefix(mut self, prefix: &str) -> PrivateBuilder {
+        self.config.chapter_prefix = prefix.to_string();
+        self
+    }
+
+    pub fn removed_stub(mut self, removed_stub: bool) -> PrivateBuilder {
+        self.config.removed_stub = removed_stub;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> PrivateBuilder {
+        self.config.strict = strict;
+        self
+    }
+
+    /// Labels ordered from least to most sensitive, see [`Private::with_levels`]
+    pub fn levels(mut self, levels: &[&str]) -> PrivateBuilder {
+        self.levels = Some(levels.iter().map(|level| level.to_string()).collect());
+        self
+    }
+
+    /// Highest level treated as public
+    pub fn threshold(mut self, threshold: &str) -> PrivateBuilder {
+        self.config.threshold = Some(threshold.to_string());
+        self
+    }
+
+    /// Encrypt the archive written by the `remove-archive` option
+    pub fn encryptor(mut self, encryptor: impl Encryptor + 'static) -> PrivateBuilder {
+        self.encryptor = Some(Box::new(encryptor));
+        self
+    }
+
+    pub fn build(self) -> Private {
+        let mut private = Private::with_config(self.config);
+        if let Some(name) = self.name {
+            private.name = name;
+        }
+        private.levels = self.levels;
+        private.encryptor = self.encryptor;
+        private
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mdbook::book::{Book, Chapter};
+    use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
+    use mdbook::BookItem;
+
+    fn run(private: &Private, table: Option<&str>, content: &str) -> String {
+        let preprocessor = match table {
+            Some(table) => format!(r#"{{ "{}": {} }}"#, private.name(), table),
+            None => "{}".to_string(),
+        };
+        let input_json = format!(
+            r##"[
+                {{
+                    "root": "/path/to/book",
+                    "config": {{
+                        "book": {{
+                            "authors": ["AUTHOR"],
+                            "language": "en",
+                            "multilingual": false,
+                            "src": "src",
+                            "title": "TITLE"
+                        }},
+                        "preprocessor": {}
+                    }},
+                    "renderer": "html",
+                    "mdbook_version": "0.4.32"
+                }},
+                {{
+                    "sections": [],
+                    "__non_exhaustive": null
+                }}
+            ]"##,
+            preprocessor
+        );
+        let (ctx, _) = CmdPreprocessor::parse_input(input_json.as_bytes()).unwrap();
+        let mut book = Book::new();
+        book.push_item(Chapter::new(
+            "Chapter 1",
+            content.to_string(),
+            "chapter_1.md",
+            vec![],
+        ));
+
+        let book = private.run(&ctx, book).unwrap();
+        match book.iter().next() {
+            Some(BookItem::Chapter(chapter)) => chapter.content.clone(),
+            _ => panic!("expected a chapter"),
+        }
+    }
+
+    #[test]
+    fn builder_remove() {
+        let private = PrivateBuilder::new().remove(true).build();
+        assert_eq!(private.name(), "private");
+        assert_eq!(
+            run(&private, None, "Before\n<!--private\nSecret\n-->\nAfter"),
+            "Before\nAfter"
+        );
+    }
+
+    #[test]
+    fn builder_notice_and_name() {
+        let private = PrivateBuilder::new()
+            .name("internal")
+            .notice("INTERNAL")
+            .css_class(true)
+            .build();
+        assert_eq!(private.name(), "internal");
+
+        let content = run(&private, None, "<!--private\nSecret\n-->\n");
+        assert!(content.contains(">INTERNAL</span>"), "{}", content);
+        assert!(content.contains("class='mdbook-private'"), "{}", content);
+    }
+
+    #[test]
+    fn book_table_merges_over_builder() {
+        let private = PrivateBuilder::new().remove(true).notice("BUILDER").build();
+        let content = run(
+            &private,
+            Some(r#"{ "notice": "TABLE" }"#),
+            "Before\n<!--private\nSecret\n-->\nAfter",
+        );
+        assert_eq!(content, "Before\nAfter");
+
+        let private = PrivateBuilder::new()
+            .notice("BUILDER")
+            .css_class(true)
+            .build();
+        let content = run(
+            &private,
+            Some(r#"{ "notice": "TABLE" }"#),
+            "<!--private\nSecret\n-->\n",
+        );
+        assert!(content.contains(">TABLE</span>"), "{}", content);
+        assert!(content.contains("class='mdbook-private'"), "{}", content);
+        assert!(content.contains("Secret"), "{}", content);
+    }
+}
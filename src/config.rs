@@ -0,0 +1,1754 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use mdbook::errors::Error;
+use regex::Regex;
+use toml::value::Table;
+
+use crate::pipeline::PostProcessor;
+use crate::transform::BlockTransform;
+
+const DEFAULT_NOTICE: &str = "CONFIDENTIAL";
+const DEFAULT_CHAPTER_PREFIX: &str = "_";
+const DEFAULT_GATE_COOKIE: &str = "mdbook-private";
+const DEFAULT_STUB_TEXT: &str = "This chapter is not available in this edition.";
+const DEFAULT_GROUP_TITLE: &str = "Internal";
+const DEFAULT_EMPTY_PART_NOTE: &str = "{title} (not available in this edition)";
+const DEFAULT_TITLE_PREFIX: &str = "title:";
+
+/// Keys mdbook itself reads from every preprocessor table
+const MDBOOK_KEYS: &[&str] = &["command", "renderers", "before", "after", "optional"];
+
+/// Every key understood under `[preprocessor.private]`
+const KNOWN_KEYS: &[&str] = &[
+    "enabled",
+    "config-path",
+    "ignore-file",
+    "remove",
+    "remove-preserve-lines",
+    "remove-on-branches",
+    "keep-on-branches",
+    "always-remove",
+    "review-keyword",
+    "style",
+    "style-variant",
+    "verbatim",
+    "format",
+    "css-class",
+    "data-attributes",
+    "notice",
+    "box-when-no-notice",
+    "nested-notice",
+    "notice-flow",
+    "first-line-title",
+    "first-line-title-prefix",
+    "notice-href",
+    "renderer-notices",
+    "content-style",
+    "notice-style",
+    "notice-styles",
+    "notice-styles-by",
+    "chapter-prefix",
+    "chapter-prefixes",
+    "chapter-prefix-enabled",
+    "chapter-prefix-regex",
+    "blocks-enabled",
+    "regions-only",
+    "block-requires-own-line",
+    "case-insensitive-marker",
+    "consume-trailing-newline",
+    "normalize-unicode",
+    "removed-stub",
+    "stub-text",
+    "stub-style",
+    "stub-template",
+    "toc-omitted-note",
+    "empty-part-policy",
+    "empty-part-note",
+    "chapter-banner",
+    "classification",
+    "whole-page-private",
+    "landing-remove",
+    "landing-path",
+    "group-private-chapters",
+    "group-private-title",
+    "page-count-banner",
+    "query-toggle",
+    "no-print-page",
+    "preview-mode",
+    "gate",
+    "gate-cookie",
+    "anchors",
+    "global-numbering",
+    "source-comments",
+    "markdown-safe",
+    "keep-empty",
+    "scan-link-text",
+    "preserve-leading-whitespace",
+    "post-process",
+    "block-transforms",
+    "normalize-block-whitespace",
+    "line-ending",
+    "strict",
+    "deny-unknown-keys",
+    "stamp",
+    "stamp-date",
+    "removed-digest",
+    "drop-empty-after-removal",
+    "dry-run",
+    "preview-path",
+    "audit-path",
+    "remove-archive",
+    "require-matches",
+    "orphan-check",
+    "max-depth",
+    "max-blocks",
+    "levels",
+    "threshold",
+    "warn-unused-levels",
+    "require-declared-labels",
+    "renderers-allow",
+    "renderers-deny",
+    "metadata-renderers",
+];
+
+/// Markup used for retained private blocks when `style` is enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StyleVariant {
+    /// A blockquote with the notice in its corner
+    #[default]
+    Blockquote,
+    /// An inline span blurred by the theme until hovered
+    Spoiler,
+    /// A block overlaid with the notice as a watermark
+    Watermark,
+    /// Highlighted text, one `<mark>` per paragraph, without a notice
+    Mark,
+    /// The source text in an inert `<template>`, left for a custom element to render
+    Template,
+}
+
+impl StyleVariant {
+    fn parse(value: &str) -> Result<StyleVariant, Error> {
+        match value {
+            "blockquote" => Ok(StyleVariant::Blockquote),
+            "spoiler" => Ok(StyleVariant::Spoiler),
+            "watermark" => Ok(StyleVariant::Watermark),
+            "mark" => Ok(StyleVariant::Mark),
+            "template" => Ok(StyleVariant::Template),
+            _ => Err(Error::msg(format!("Unknown `style-variant` '{}'", value))),
+        }
+    }
+}
+
+/// Placement of the notice within a styled blockquote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoticeFlow {
+    /// Positioned absolutely in the top right corner
+    #[default]
+    Absolute,
+    /// A normal-flow line above the content
+    Block,
+}
+
+impl NoticeFlow {
+    fn parse(value: &str) -> Result<NoticeFlow, Error> {
+        match value {
+            "absolute" => Ok(NoticeFlow::Absolute),
+            "block" => Ok(NoticeFlow::Block),
+            _ => Err(Error::msg(format!("Unknown `notice-flow` '{}'", value))),
+        }
+    }
+}
+
+/// How each styled block picks its notice style from `notice-styles`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoticeStylesBy {
+    /// In turn, by the position of the block in its chapter
+    #[default]
+    Index,
+    /// By a hash of the block's content, so a block keeps its style as others are added
+    Hash,
+}
+
+impl NoticeStylesBy {
+    fn parse(value: &str) -> Result<NoticeStylesBy, Error> {
+        match value {
+            "index" => Ok(NoticeStylesBy::Index),
+            "hash" => Ok(NoticeStylesBy::Hash),
+            _ => Err(Error::msg(format!(
+                "Unknown `notice-styles-by` '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+/// How retained private blocks are laid out in a chapter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Each block stays where it was written
+    #[default]
+    Inline,
+    /// Each block becomes a footnote reference, defined at the end of the chapter
+    Endnotes,
+}
+
+impl Format {
+    fn parse(value: &str) -> Result<Format, Error> {
+        match value {
+            "inline" => Ok(Format::Inline),
+            "endnotes" => Ok(Format::Endnotes),
+            _ => Err(Error::msg(format!("Unknown `format` '{}'", value))),
+        }
+    }
+}
+
+/// Line break used for the newlines the preprocessor inserts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// Always `\n`
+    #[default]
+    Lf,
+    /// Always `\r\n`
+    Crlf,
+    /// `\r\n` in chapters that use it, `\n` otherwise
+    Preserve,
+}
+
+impl LineEnding {
+    fn parse(value: &str) -> Result<LineEnding, Error> {
+        match value {
+            "lf" => Ok(LineEnding::Lf),
+            "crlf" => Ok(LineEnding::Crlf),
+            "preserve" => Ok(LineEnding::Preserve),
+            _ => Err(Error::msg(format!("Unknown `line-ending` '{}'", value))),
+        }
+    }
+}
+
+/// What a prefix of `chapter-prefixes` does to the chapters whose file name starts with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixAction {
+    /// Private, like chapters matching `chapter-prefix`
+    Remove,
+    /// Listed in the navigation as a draft, without a page, in every build
+    Draft,
+    /// Left alone, even if the file name also matches another prefix
+    Ignore,
+}
+
+impl PrefixAction {
+    fn parse(value: &str) -> Result<PrefixAction, Error> {
+        match value {
+            "remove" => Ok(PrefixAction::Remove),
+            "draft" => Ok(PrefixAction::Draft),
+            "ignore" => Ok(PrefixAction::Ignore),
+            _ => Err(Error::msg(format!(
+                "Unknown `chapter-prefixes` action '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+/// Which of the private blocks nested in one another show a notice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NestedNotice {
+    /// Every block shows its notice, so they stack up
+    #[default]
+    Stack,
+    /// Only blocks holding no other block show their notice
+    Innermost,
+}
+
+impl NestedNotice {
+    fn parse(value: &str) -> Result<NestedNotice, Error> {
+        match value {
+            "stack" => Ok(NestedNotice::Stack),
+            "innermost" => Ok(NestedNotice::Innermost),
+            _ => Err(Error::msg(format!("Unknown `nested-notice` '{}'", value))),
+        }
+    }
+}
+
+/// What happens to a part title once every chapter of the part is removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyPartPolicy {
+    /// Leave the part title alone
+    #[default]
+    Keep,
+    /// Remove the part title
+    Remove,
+    /// Replace the part title with the `empty-part-note`
+    Note,
+}
+
+impl EmptyPartPolicy {
+    fn parse(value: &str) -> Result<EmptyPartPolicy, Error> {
+        match value {
+            "keep" => Ok(EmptyPartPolicy::Keep),
+            "remove" => Ok(EmptyPartPolicy::Remove),
+            "note" => Ok(EmptyPartPolicy::Note),
+            _ => Err(Error::msg(format!(
+                "Unknown `empty-part-policy` '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+/// Alternative renderings of a keep build, for reviewers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewMode {
+    /// Mark each block with whether it survives the public build
+    Diff,
+}
+
+impl PreviewMode {
+    fn parse(value: &str) -> Result<PreviewMode, Error> {
+        match value {
+            "diff" => Ok(PreviewMode::Diff),
+            _ => Err(Error::msg(format!("Unknown `preview-mode` '{}'", value))),
+        }
+    }
+}
+
+/// Client-side condition under which theme scripts reveal retained blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    /// Revealed when the browser holds the `gate-cookie` cookie
+    Cookie,
+}
+
+/// What to do about chapters left out of `SUMMARY.md` that hold private content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanCheck {
+    /// Log a warning naming each file
+    Warn,
+    /// Fail the build
+    Error,
+}
+
+impl OrphanCheck {
+    fn parse(value: &str) -> Result<OrphanCheck, Error> {
+        match value {
+            "warn" => Ok(OrphanCheck::Warn),
+            "error" => Ok(OrphanCheck::Error),
+            _ => Err(Error::msg(format!("Unknown `orphan-check` '{}'", value))),
+        }
+    }
+}
+
+impl Gate {
+    fn parse(value: &str) -> Result<Gate, Error> {
+        match value {
+            "cookie" => Ok(Gate::Cookie),
+            _ => Err(Error::msg(format!("Unknown `gate` '{}'", value))),
+        }
+    }
+}
+
+/// Preprocessor configuration as read from `[preprocessor.private]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub enabled: bool,
+    /// File the table was merged with, relative to the book root
+    pub config_path: Option<PathBuf>,
+    /// File listing chapters left untouched, relative to the book root
+    pub ignore_file: Option<PathBuf>,
+    pub remove: bool,
+    pub remove_preserve_lines: bool,
+    /// Git branches built with `remove = true`, `*` matching any characters
+    pub remove_on_branches: Vec<String>,
+    /// Git branches built with `remove = false`, `*` matching any characters
+    pub keep_on_branches: Vec<String>,
+    /// Regexes stripped from all chapter content, whatever the mode
+    pub always_remove: Vec<String>,
+    /// Keyword of the reviewer comment markers, shown as sidenotes in keep mode only
+    pub review_keyword: Option<String>,
+    pub style: bool,
+    pub style_variant: StyleVariant,
+    /// Keep the text between the markers byte for byte when `style` is off
+    pub verbatim: bool,
+    pub format: Format,
+    pub css_class: bool,
+    /// Describe styled blocks to theme scripts with `data-private-*` attributes
+    pub data_attributes: bool,
+    pub notice: String,
+    /// Keep the box around retained blocks when the notice is empty
+    pub box_when_no_notice: bool,
+    pub nested_notice: NestedNotice,
+    pub notice_flow: NoticeFlow,
+    /// Show a leading heading or prefixed line of a block as its notice
+    pub first_line_title: bool,
+    pub first_line_title_prefix: String,
+    /// Link target of the notice, such as a data-handling policy
+    pub notice_href: Option<String>,
+    /// Notice used in place of `notice` by each renderer listed
+    pub renderer_notices: BTreeMap<String, String>,
+    /// Inline CSS of styled blocks, replacing the built-in style
+    pub content_style: Option<String>,
+    /// Inline CSS of the notice, replacing the built-in style
+    pub notice_style: Option<String>,
+    /// Inline CSS of the notice assigned to blocks in turn, taking precedence over `notice_style`
+    pub notice_styles: Vec<String>,
+    pub notice_styles_by: NoticeStylesBy,
+    pub chapter_prefix: String,
+    /// File name prefixes with their own action, the longest matching one taking precedence
+    /// over `chapter_prefix`
+    pub chapter_prefixes: BTreeMap<String, PrefixAction>,
+    pub chapter_prefix_enabled: bool,
+    /// Match `chapter_prefix` as a regex against the file name
+    pub chapter_prefix_regex: bool,
+    pub blocks_enabled: bool,
+    /// Only handle private blocks between region start and end comments
+    pub regions_only: bool,
+    /// Render sections sharing their line with other text inline
+    pub block_requires_own_line: bool,
+    pub case_insensitive_marker: bool,
+    /// Take the line break following a closing `-->` as part of the block
+    pub consume_trailing_newline: bool,
+    pub normalize_unicode: bool,
+    pub removed_stub: bool,
+    pub stub_text: String,
+    pub stub_style: Option<String>,
+    /// Content of stub pages, `{name}` and `{path}` giving the chapter's name and source path
+    pub stub_template: Option<String>,
+    /// Suffix of chapters whose sub chapters were removed, `{count}` giving their number
+    pub toc_omitted_note: Option<String>,
+    pub empty_part_policy: EmptyPartPolicy,
+    /// Part title shown by `EmptyPartPolicy::Note`, `{title}` giving the original title
+    pub empty_part_note: String,
+    pub chapter_banner: Option<String>,
+    /// Value of the `classification` frontmatter key given to chapters with private content
+    pub classification: Option<String>,
+    /// Treat chapters holding nothing but a heading and one private block as private chapters
+    pub whole_page_private: bool,
+    /// Remove the private blocks of the landing page, whatever the mode
+    pub landing_remove: bool,
+    /// Source path of the landing page, defaults to the first chapter of the book
+    pub landing_path: Option<PathBuf>,
+    /// Move private chapters under a part title at the end of a keep build
+    pub group_private_chapters: bool,
+    /// Part title private chapters are grouped under
+    pub group_private_title: String,
+    /// Tell how many private notes each page of a keep build holds
+    pub page_count_banner: bool,
+    pub query_toggle: Option<String>,
+    /// Give retained blocks a class the theme can hide on the print page
+    pub no_print_page: bool,
+    pub preview_mode: Option<PreviewMode>,
+    pub gate: Option<Gate>,
+    /// Name of the cookie revealing blocks with `Gate::Cookie`
+    pub gate_cookie: String,
+    pub anchors: bool,
+    /// Number retained blocks across the whole book rather than per chapter
+    pub global_numbering: bool,
+    pub source_comments: bool,
+    pub markdown_safe: bool,
+    pub keep_empty: bool,
+    pub scan_link_text: bool,
+    pub preserve_leading_whitespace: bool,
+    pub post_processors: Vec<PostProcessor>,
+    /// Changes applied to the content of retained blocks, in order
+    pub block_transforms: Vec<BlockTransform>,
+    /// Trim trailing whitespace and long runs of blank lines within retained blocks
+    pub normalize_block_whitespace: bool,
+    pub line_ending: LineEnding,
+    pub strict: bool,
+    pub deny_unknown_keys: bool,
+    pub stamp: bool,
+    /// Date written in the stamp comment, defaults to the date of the build
+    pub stamp_date: Option<String>,
+    /// Append a SHA-256 digest of the content removed from each chapter
+    pub removed_digest: bool,
+    /// Turn chapters left empty by the removal of their blocks into drafts
+    pub drop_empty_after_removal: bool,
+    pub dry_run: bool,
+    /// Where the dry-run preview is written, relative to the book root
+    pub preview_path: Option<PathBuf>,
+    /// Where the audit of private blocks is written, relative to the book root
+    pub audit_path: Option<PathBuf>,
+    /// Where removed content is archived, relative to the book root
+    pub remove_archive: Option<PathBuf>,
+    pub require_matches: bool,
+    /// Look for files with private content that the book leaves out
+    pub orphan_check: Option<OrphanCheck>,
+    /// Deepest chapter nesting accepted, top-level chapters being at depth 1
+    pub max_depth: Option<usize>,
+    /// Most private blocks accepted in a single chapter
+    pub max_blocks: Option<usize>,
+    /// Renderer names the preprocessor runs for, `*` matching any characters
+    pub renderers_allow: Vec<String>,
+    /// Labels ordered from least to most sensitive
+    pub levels: Vec<String>,
+    /// Highest level treated as public, see `levels`
+    pub threshold: Option<String>,
+    /// Warn about `levels` that no block is labelled with
+    pub warn_unused_levels: bool,
+    /// Fail on labels that aren't one of the `levels`
+    pub require_declared_labels: bool,
+    /// Renderer names the preprocessor never runs for, taking precedence over the allow list
+    pub renderers_deny: Vec<String>,
+    /// Renderers given placeholders and region metadata instead of styled blocks
+    pub metadata_renderers: Vec<String>,
+    /// Language of the current build, taken from `book.language`
+    pub language: Option<String>,
+    /// Renderer of the current build, taken from the preprocessor context
+    pub renderer: Option<String>,
+    /// Patterns of the chapters left untouched, read from the `ignore-file`
+    pub ignored_chapters: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: true,
+            config_path: None,
+            ignore_file: None,
+            remove: false,
+            remove_preserve_lines: false,
+            remove_on_branches: Vec::new(),
+            keep_on_branches: Vec::new(),
+            always_remove: Vec::new(),
+            review_keyword: None,
+            style: true,
+            style_variant: StyleVariant::default(),
+            verbatim: false,
+            format: Format::default(),
+            css_class: false,
+            data_attributes: false,
+            notice: DEFAULT_NOTICE.to_string(),
+            box_when_no_notice: true,
+            nested_notice: NestedNotice::default(),
+            notice_flow: NoticeFlow::default(),
+            first_line_title: false,
+            first_line_title_prefix: DEFAULT_TITLE_PREFIX.to_string(),
+            notice_href: None,
+            renderer_notices: BTreeMap::new(),
+            content_style: None,
+            notice_style: None,
+            notice_styles: Vec::new(),
+            notice_styles_by: NoticeStylesBy::default(),
+            chapter_prefix: DEFAULT_CHAPTER_PREFIX.to_string(),
+            chapter_prefixes: BTreeMap::new(),
+            chapter_prefix_enabled: true,
+            chapter_prefix_regex: false,
+            blocks_enabled: true,
+            regions_only: false,
+            block_requires_own_line: false,
+            case_insensitive_marker: false,
+            consume_trailing_newline: true,
+            normalize_unicode: false,
+            removed_stub: false,
+            stub_text: DEFAULT_STUB_TEXT.to_string(),
+            stub_style: None,
+            stub_template: None,
+            toc_omitted_note: None,
+            empty_part_policy: EmptyPartPolicy::default(),
+            empty_part_note: DEFAULT_EMPTY_PART_NOTE.to_string(),
+            chapter_banner: None,
+            classification: None,
+            whole_page_private: false,
+            landing_remove: false,
+            landing_path: None,
+            group_private_chapters: false,
+            group_private_title: DEFAULT_GROUP_TITLE.to_string(),
+            page_count_banner: false,
+            query_toggle: None,
+            no_print_page: false,
+            preview_mode: None,
+            gate: None,
+            gate_cookie: DEFAULT_GATE_COOKIE.to_string(),
+            anchors: false,
+            global_numbering: false,
+            source_comments: false,
+            markdown_safe: false,
+            keep_empty: false,
+            scan_link_text: false,
+            preserve_leading_whitespace: false,
+            post_processors: Vec::new(),
+            block_transforms: Vec::new(),
+            normalize_block_whitespace: false,
+            line_ending: LineEnding::default(),
+            strict: false,
+            deny_unknown_keys: false,
+            stamp: false,
+            stamp_date: None,
+            removed_digest: false,
+            drop_empty_after_removal: false,
+            dry_run: false,
+            preview_path: None,
+            audit_path: None,
+            remove_archive: None,
+            require_matches: false,
+            orphan_check: None,
+            max_depth: None,
+            max_blocks: None,
+            renderers_allow: Vec::new(),
+            levels: Vec::new(),
+            threshold: None,
+            warn_unused_levels: false,
+            require_declared_labels: false,
+            renderers_deny: Vec::new(),
+            metadata_renderers: Vec::new(),
+            language: None,
+            renderer: None,
+            ignored_chapters: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Build the configuration from the preprocessor table, falling back to defaults
+    pub(crate) fn parse(table: Option<&Table>) -> Result<Config, Error> {
+        Config::parse_onto(Config::default(), table)
+    }
+
+    /// Apply the options set in the preprocessor table over an existing configuration, leaving
+    /// the options the table doesn't set as they are
+    pub(crate) fn parse_onto(mut cfg: Config, table: Option<&Table>) -> Result<Config, Error> {
+        let Some(table) = table else {
+            return Ok(cfg);
+        };
+
+        if let Some(enabled) = get_bool(table, "enabled")? {
+            cfg.enabled = enabled;
+        }
+        if let Some(path) = get_str(table, "config-path")? {
+            cfg.config_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = get_str(table, "ignore-file")? {
+            cfg.ignore_file = Some(PathBuf::from(path));
+        }
+        if let Some(remove) = get_bool(table, "remove")? {
+            cfg.remove = remove;
+        }
+        if let Some(preserve) = get_bool(table, "remove-preserve-lines")? {
+            cfg.remove_preserve_lines = preserve;
+        }
+        if let Some(branches) = get_str_array(table, "remove-on-branches")? {
+            cfg.remove_on_branches = branches.into_iter().map(String::from).collect();
+        }
+        if let Some(branches) = get_str_array(table, "keep-on-branches")? {
+            cfg.keep_on_branches = branches.into_iter().map(String::from).collect();
+        }
+        if let Some(patterns) = get_str_array(table, "always-remove")? {
+            cfg.always_remove = patterns.into_iter().map(String::from).collect();
+        }
+        if let Some(keyword) = get_str(table, "review-keyword")? {
+            cfg.review_keyword = Some(keyword.to_string());
+        }
+        if let Some(style) = get_bool(table, "style")? {
+            cfg.style = style;
+        }
+        if let Some(variant) = get_str(table, "style-variant")? {
+            cfg.style_variant = StyleVariant::parse(variant)?;
+        }
+        if let Some(verbatim) = get_bool(table, "verbatim")? {
+            cfg.verbatim = verbatim;
+        }
+        if let Some(format) = get_str(table, "format")? {
+            cfg.format = Format::parse(format)?;
+        }
+        if let Some(css_class) = get_bool(table, "css-class")? {
+            cfg.css_class = css_class;
+        }
+        if let Some(data_attributes) = get_bool(table, "data-attributes")? {
+            cfg.data_attributes = data_attributes;
+        }
+        if let Some(notice) = get_str(table, "notice")? {
+            cfg.notice = notice.to_string();
+        }
+        if let Some(boxed) = get_bool(table, "box-when-no-notice")? {
+            cfg.box_when_no_notice = boxed;
+        }
+        if let Some(nested) = get_str(table, "nested-notice")? {
+            cfg.nested_notice = NestedNotice::parse(nested)?;
+        }
+        if let Some(flow) = get_str(table, "notice-flow")? {
+            cfg.notice_flow = NoticeFlow::parse(flow)?;
+        }
+        if let Some(title) = get_bool(table, "first-line-title")? {
+            cfg.first_line_title = title;
+        }
+        if let Some(prefix) = get_str(table, "first-line-title-prefix")? {
+            cfg.first_line_title_prefix = prefix.to_string();
+        }
+        if let Some(href) = get_str(table, "notice-href")? {
+            cfg.notice_href = Some(href.to_string());
+        }
+        if let Some(notices) = get_str_table(table, "renderer-notices")? {
+            cfg.renderer_notices = notices;
+        }
+        if let Some(style) = get_str(table, "content-style")? {
+            cfg.content_style = Some(style.to_string());
+        }
+        if let Some(style) = get_str(table, "notice-style")? {
+            cfg.notice_style = Some(style.to_string());
+        }
+        if let Some(styles) = get_str_array(table, "notice-styles")? {
+            cfg.notice_styles = styles.into_iter().map(String::from).collect();
+        }
+        if let Some(by) = get_str(table, "notice-styles-by")? {
+            cfg.notice_styles_by = NoticeStylesBy::parse(by)?;
+        }
+        if let Some(prefix) = get_str(table, "chapter-prefix")? {
+            cfg.chapter_prefix = prefix.to_string();
+        }
+        if let Some(prefixes) = get_str_table(table, "chapter-prefixes")? {
+            cfg.chapter_prefixes = prefixes
+                .into_iter()
+                .map(|(prefix, action)| Ok((prefix, PrefixAction::parse(&action)?)))
+                .collect::<Result<_, Error>>()?;
+        }
+        if let Some(enabled) = get_bool(table, "chapter-prefix-enabled")? {
+            cfg.chapter_prefix_enabled = enabled;
+        }
+        if let Some(regex) = get_bool(table, "chapter-prefix-regex")? {
+            cfg.chapter_prefix_regex = regex;
+        }
+        if let Some(regions_only) = get_bool(table, "regions-only")? {
+            cfg.regions_only = regions_only;
+        }
+        if let Some(enabled) = get_bool(table, "blocks-enabled")? {
+            cfg.blocks_enabled = enabled;
+        }
+        if let Some(consume) = get_bool(table, "consume-trailing-newline")? {
+            cfg.consume_trailing_newline = consume;
+        }
+        if let Some(own_line) = get_bool(table, "block-requires-own-line")? {
+            cfg.block_requires_own_line = own_line;
+        }
+        if let Some(insensitive) = get_bool(table, "case-insensitive-marker")? {
+            cfg.case_insensitive_marker = insensitive;
+        }
+        if let Some(normalize) = get_bool(table, "normalize-unicode")? {
+            cfg.normalize_unicode = normalize;
+        }
+        if let Some(removed_stub) = get_bool(table, "removed-stub")? {
+            cfg.removed_stub = removed_stub;
+        }
+        if let Some(text) = get_str(table, "stub-text")? {
+            cfg.stub_text = text.to_string();
+        }
+        if let Some(style) = get_str(table, "stub-style")? {
+            cfg.stub_style = Some(style.to_string());
+        }
+        if let Some(template) = get_str(table, "stub-template")? {
+            cfg.stub_template = Some(template.to_string());
+        }
+        if let Some(note) = get_str(table, "toc-omitted-note")? {
+            cfg.toc_omitted_note = Some(note.to_string());
+        }
+        if let Some(policy) = get_str(table, "empty-part-policy")? {
+            cfg.empty_part_policy = EmptyPartPolicy::parse(policy)?;
+        }
+        if let Some(note) = get_str(table, "empty-part-note")? {
+            cfg.empty_part_note = note.to_string();
+        }
+        if let Some(banner) = get_str(table, "chapter-banner")? {
+            cfg.chapter_banner = Some(banner.to_string());
+        }
+        if let Some(classification) = get_str(table, "classification")? {
+            cfg.classification = Some(classification.to_string());
+        }
+        if let Some(whole_page) = get_bool(table, "whole-page-private")? {
+            cfg.whole_page_private = whole_page;
+        }
+        if let Some(landing_remove) = get_bool(table, "landing-remove")? {
+            cfg.landing_remove = landing_remove;
+        }
+        if let Some(path) = get_str(table, "landing-path")? {
+            cfg.landing_path = Some(PathBuf::from(path));
+        }
+        if let Some(group) = get_bool(table, "group-private-chapters")? {
+            cfg.group_private_chapters = group;
+        }
+        if let Some(title) = get_str(table, "group-private-title")? {
+            cfg.group_private_title = title.to_string();
+        }
+        if let Some(count) = get_bool(table, "page-count-banner")? {
+            cfg.page_count_banner = count;
+        }
+        if let Some(param) = get_str(table, "query-toggle")? {
+            cfg.query_toggle = Some(param.to_string());
+        }
+        if let Some(no_print_page) = get_bool(table, "no-print-page")? {
+            cfg.no_print_page = no_print_page;
+        }
+        if let Some(check) = get_str(table, "orphan-check")? {
+            cfg.orphan_check = Some(OrphanCheck::parse(check)?);
+        }
+        if let Some(mode) = get_str(table, "preview-mode")? {
+            cfg.preview_mode = Some(PreviewMode::parse(mode)?);
+        }
+        if let Some(gate) = get_str(table, "gate")? {
+            cfg.gate = Some(Gate::parse(gate)?);
+        }
+        if let Some(cookie) = get_str(table, "gate-cookie")? {
+            cfg.gate_cookie = cookie.to_string();
+        }
+        if let Some(anchors) = get_bool(table, "anchors")? {
+            cfg.anchors = anchors;
+        }
+        if let Some(global) = get_bool(table, "global-numbering")? {
+            cfg.global_numbering = global;
+        }
+        if let Some(comments) = get_bool(table, "source-comments")? {
+            cfg.source_comments = comments;
+        }
+        if let Some(markdown_safe) = get_bool(table, "markdown-safe")? {
+            cfg.markdown_safe = markdown_safe;
+        }
+        if let Some(keep_empty) = get_bool(table, "keep-empty")? {
+            cfg.keep_empty = keep_empty;
+        }
+        if let Some(scan) = get_bool(table, "scan-link-text")? {
+            cfg.scan_link_text = scan;
+        }
+        if let Some(preserve) = get_bool(table, "preserve-leading-whitespace")? {
+            cfg.preserve_leading_whitespace = preserve;
+        }
+        if let Some(transforms) = get_str_array(table, "block-transforms")? {
+            cfg.block_transforms = transforms
+                .into_iter()
+                .map(BlockTransform::parse)
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(normalize) = get_bool(table, "normalize-block-whitespace")? {
+            cfg.normalize_block_whitespace = normalize;
+        }
+        if let Some(stages) = get_str_array(table, "post-process")? {
+            cfg.post_processors = stages
+                .into_iter()
+                .map(PostProcessor::parse)
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(ending) = get_str(table, "line-ending")? {
+            cfg.line_ending = LineEnding::parse(ending)?;
+        }
+        if let Some(strict) = get_bool(table, "strict")? {
+            cfg.strict = strict;
+        }
+        if let Some(deny) = get_bool(table, "deny-unknown-keys")? {
+            cfg.deny_unknown_keys = deny;
+        }
+        if let Some(stamp) = get_bool(table, "stamp")? {
+            cfg.stamp = stamp;
+        }
+        if let Some(date) = get_str(table, "stamp-date")? {
+            cfg.stamp_date = Some(date.to_string());
+        }
+        if let Some(digest) = get_bool(table, "removed-digest")? {
+            cfg.removed_digest = digest;
+        }
+        if let Some(drop_empty) = get_bool(table, "drop-empty-after-removal")? {
+            cfg.drop_empty_after_removal = drop_empty;
+        }
+        if let Some(dry_run) = get_bool(table, "dry-run")? {
+            cfg.dry_run = dry_run;
+        }
+        if let Some(path) = get_str(table, "preview-path")? {
+            cfg.preview_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = get_str(table, "audit-path")? {
+            cfg.audit_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = get_str(table, "remove-archive")? {
+            cfg.remove_archive = Some(PathBuf::from(path));
+        }
+        if let Some(require_matches) = get_bool(table, "require-matches")? {
+            cfg.require_matches = require_matches;
+        }
+        if let Some(depth) = get_usize(table, "max-depth")? {
+            cfg.max_depth = Some(depth);
+        }
+        if let Some(blocks) = get_usize(table, "max-blocks")? {
+            cfg.max_blocks = Some(blocks);
+        }
+        if let Some(levels) = get_str_array(table, "levels")? {
+            cfg.levels = levels.into_iter().map(String::from).collect();
+        }
+        if let Some(threshold) = get_str(table, "threshold")? {
+            cfg.threshold = Some(threshold.to_string());
+        }
+        if let Some(warn_unused) = get_bool(table, "warn-unused-levels")? {
+            cfg.warn_unused_levels = warn_unused;
+        }
+        if let Some(require) = get_bool(table, "require-declared-labels")? {
+            cfg.require_declared_labels = require;
+        }
+        if let Some(renderers) = get_str_array(table, "renderers-allow")? {
+            cfg.renderers_allow = renderers.into_iter().map(String::from).collect();
+        }
+        if let Some(renderers) = get_str_array(table, "renderers-deny")? {
+            cfg.renderers_deny = renderers.into_iter().map(String::from).collect();
+        }
+        if let Some(renderers) = get_str_array(table, "metadata-renderers")? {
+            cfg.metadata_renderers = renderers.into_iter().map(String::from).collect();
+        }
+
+        if cfg.chapter_prefix_regex {
+            Regex::new(&cfg.chapter_prefix).map_err(|e| {
+                Error::new(e).context(format!(
+                    "Invalid `chapter-prefix` regex '{}'",
+                    cfg.chapter_prefix
+                ))
+            })?;
+        }
+
+        for pattern in &cfg.always_remove {
+            Regex::new(pattern).map_err(|e| {
+                Error::new(e).context(format!("Invalid `always-remove` regex '{}'", pattern))
+            })?;
+        }
+
+        if let Some(keyword) = &cfg.review_keyword {
+            let valid = keyword
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if keyword.is_empty() || !valid {
+                return Err(Error::msg(format!(
+                    "Invalid `review-keyword` '{}'",
+                    keyword
+                )));
+            }
+            if keyword.starts_with("private") || keyword == "public" {
+                return Err(Error::msg(format!(
+                    "`review-keyword` '{}' clashes with the private and public markers",
+                    keyword
+                )));
+            }
+        }
+
+        let unknown = unknown_keys(table);
+        if !unknown.is_empty() {
+            if cfg.deny_unknown_keys || cfg.strict {
+                return Err(Error::msg(format!(
+                    "Unknown private preprocessor configuration: {}",
+                    unknown.join("; ")
+                )));
+            }
+            for problem in &unknown {
+                warn!("Ignoring {}", problem);
+            }
+        }
+
+        Ok(cfg)
+    }
+
+    /// Parse and validate a `[preprocessor.private]` table
+    ///
+    /// This is what the preprocessor runs on its configuration, usable on its own to check a
+    /// table without building a book.
+    pub fn from_toml_value(table: &Table) -> Result<Config, Error> {
+        let cfg = Config::parse(Some(table))?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Whether the preprocessor should run for the given renderer
+    pub fn supports_renderer(&self, renderer: &str) -> bool {
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| matches_wildcard(pattern, renderer))
+        };
+
+        if matches(&self.renderers_deny) {
+            return false;
+        }
+        if !self.renderers_allow.is_empty() {
+            return matches(&self.renderers_allow);
+        }
+        renderer != "not-supported"
+    }
+
+    /// Whether the given git branch is built with `remove`, if it matches either branch list
+    ///
+    /// A branch matching both lists is built with `remove`, erring on the side of the public
+    /// build.
+    pub fn remove_on_branch(&self, branch: &str) -> Option<bool> {
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| matches_wildcard(pattern, branch))
+        };
+
+        if matches(&self.remove_on_branches) {
+            Some(true)
+        } else if matches(&self.keep_on_branches) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Whether retained blocks are handed to the renderer as metadata rather than markup
+    pub fn emits_metadata(&self) -> bool {
+        self.renderer.as_deref().is_some_and(|renderer| {
+            self.metadata_renderers
+                .iter()
+                .any(|pattern| matches_wildcard(pattern, renderer))
+        })
+    }
+
+    /// Whether a block with the given label is public at the configured threshold
+    pub fn within_threshold(&self, label: Option<&str>) -> bool {
+        let (Some(label), Some(threshold)) = (label, &self.threshold) else {
+            return false;
+        };
+        let rank = |name: &str| self.levels.iter().position(|level| level == name);
+        matches!((rank(label), rank(threshold)), (Some(level), Some(max)) if level <= max)
+    }
+
+    /// Check for option combinations that contradict each other
+    ///
+    /// Problems are logged as warnings, or returned as an error in `strict` mode.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut problems = Vec::new();
+
+        if !self.style && self.notice != DEFAULT_NOTICE {
+            problems.push("`notice` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.style_variant != StyleVariant::default() {
+            problems.push("`style-variant` has no effect when `style = false`".to_string());
+        }
+        if !self.style && !self.box_when_no_notice {
+            problems.push("`box-when-no-notice` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.nested_notice != NestedNotice::default() {
+            problems.push("`nested-notice` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.notice_flow != NoticeFlow::default() {
+            problems.push("`notice-flow` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.first_line_title {
+            problems.push("`first-line-title` has no effect when `style = false`".to_string());
+        }
+        if !self.style && !self.renderer_notices.is_empty() {
+            problems.push("`renderer-notices` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.notice_href.is_some() {
+            problems.push("`notice-href` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.content_style.is_some() {
+            problems.push("`content-style` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.notice_style.is_some() {
+            problems.push("`notice-style` has no effect when `style = false`".to_string());
+        }
+        if !self.style && !self.notice_styles.is_empty() {
+            problems.push("`notice-styles` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.css_class {
+            problems.push("`css-class` has no effect when `style = false`".to_string());
+        }
+        if !self.style && self.data_attributes {
+            problems.push("`data-attributes` has no effect when `style = false`".to_string());
+        }
+
+        if !self.remove && self.removed_digest {
+            problems.push("`removed-digest` has no effect unless `remove = true`".to_string());
+        }
+        if !self.style && !self.anchors && self.global_numbering {
+            problems.push(
+                "`global-numbering` has no effect without `anchors` or `style = true`".to_string(),
+            );
+        }
+        if !self.remove && self.drop_empty_after_removal {
+            problems.push(
+                "`drop-empty-after-removal` has no effect unless `remove = true`".to_string(),
+            );
+        }
+        if self.style && self.verbatim {
+            problems.push("`verbatim` has no effect unless `style = false`".to_string());
+        }
+
+        if self.remove && self.preview_mode.is_some() {
+            problems.push("`preview-mode` has no effect when `remove = true`".to_string());
+        }
+        if !self.blocks_enabled && self.regions_only {
+            problems.push("`regions-only` has no effect when `blocks-enabled = false`".to_string());
+        }
+        if self.remove && self.classification.is_some() {
+            problems.push("`classification` has no effect when `remove = true`".to_string());
+        }
+        if self.remove && self.audit_path.is_some() {
+            problems.push("`audit-path` has no effect when `remove = true`".to_string());
+        }
+        if self.levels.is_empty() && self.require_declared_labels {
+            problems.push("`require-declared-labels` has no effect without `levels`".to_string());
+        }
+        if !self.landing_remove && self.landing_path.is_some() {
+            problems.push("`landing-path` has no effect without `landing-remove`".to_string());
+        }
+        if self.gate.is_none() && self.gate_cookie != DEFAULT_GATE_COOKIE {
+            problems.push("`gate-cookie` has no effect without `gate = \"cookie\"`".to_string());
+        }
+
+        if !self.remove && self.empty_part_policy != EmptyPartPolicy::Keep {
+            problems.push("`empty-part-policy` has no effect unless `remove = true`".to_string());
+        }
+        if self.empty_part_policy != EmptyPartPolicy::Note
+            && self.empty_part_note != DEFAULT_EMPTY_PART_NOTE
+        {
+            problems.push(
+                "`empty-part-note` has no effect without `empty-part-policy = \"note\"`"
+                    .to_string(),
+            );
+        }
+
+        if !self.group_private_chapters && self.group_private_title != DEFAULT_GROUP_TITLE {
+            problems.push(
+                "`group-private-title` has no effect without `group-private-chapters`".to_string(),
+            );
+        }
+
+        if let Some(threshold) = &self.threshold {
+            // Levels may still be given through `Private::with_levels`
+            if !self.levels.is_empty() && !self.levels.contains(threshold) {
+                problems.push(format!(
+                    "`threshold` '{}' is not one of `levels`",
+                    threshold
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        if self.strict {
+            return Err(Error::msg(format!(
+                "Invalid private preprocessor configuration: {}",
+                problems.join("; ")
+            )));
+        }
+        for problem in &problems {
+            warn!("{}", problem);
+        }
+
+        Ok(())
+    }
+}
+
+/// Match a name against a pattern where `*` stands for any run of characters
+pub(crate) fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            name.char_indices()
+                .map(|(i, _)| i)
+                .chain([name.len()])
+                .any(|i| matches_wildcard(rest, &name[i..]))
+        }
+    }
+}
+
+/// Describe each unrecognized key along with the closest known key
+fn unknown_keys(table: &Table) -> Vec<String> {
+    table
+        .keys()
+        .filter(|key| !KNOWN_KEYS.contains(&key.as_str()) && !MDBOOK_KEYS.contains(&key.as_str()))
+        .map(|key| {
+            let nearest = KNOWN_KEYS
+                .iter()
+                .min_by_key(|known| edit_distance(key, known))
+                .expect("known keys are not empty");
+            format!("unknown key `{}` (did you mean `{}`?)", key, nearest)
+        })
+        .collect()
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Merge the preprocessor table over the file named by its `config-path` key
+///
+/// The file is read as JSON when its extension is `.json`, and as TOML otherwise. Keys of the
+/// table take precedence over those of the file, and a relative path is resolved against the
+/// book root.
+pub(crate) fn merge_config_file<'a>(
+    table: &'a Table,
+    root: &Path,
+) -> Result<Cow<'a, Table>, Error> {
+    let Some(path) = get_str(table, "config-path")? else {
+        return Ok(Cow::Borrowed(table));
+    };
+    let path = root.join(path);
+    let context = || format!("Unable to read `config-path` {}", path.display());
+
+    let text = fs::read_to_string(&path).map_err(|e| Error::new(e).context(context()))?;
+    let mut merged: Table = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::from_str(&text).map_err(|e| Error::new(e).context(context()))?
+    } else {
+        toml::from_str(&text).map_err(|e| Error::new(e).context(context()))?
+    };
+    merged.extend(
+        table
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+    Ok(Cow::Owned(merged))
+}
+
+fn get_bool(table: &Table, key: &str) -> Result<Option<bool>, Error> {
+    match table.get(key) {
+        Some(value) => value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| Error::msg(format!("`{}` must be a boolean", key))),
+        None => Ok(None),
+    }
+}
+
+fn get_usize(table: &Table, key: &str) -> Result<Option<usize>, Error> {
+    match table.get(key) {
+        Some(value) => value
+            .as_integer()
+            .and_then(|value| usize::try_from(value).ok())
+            .map(Some)
+            .ok_or_else(|| Error::msg(format!("`{}` must be a positive integer", key))),
+        None => Ok(None),
+    }
+}
+
+fn get_str<'a>(table: &'a Table, key: &str) -> Result<Option<&'a str>, Error> {
+    match table.get(key) {
+        Some(value) => value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| Error::msg(format!("`{}` must be a string", key))),
+        None => Ok(None),
+    }
+}
+
+fn get_str_array<'a>(table: &'a Table, key: &str) -> Result<Option<Vec<&'a str>>, Error> {
+    let error = || Error::msg(format!("`{}` must be an array of strings", key));
+    match table.get(key) {
+        Some(value) => value
+            .as_array()
+            .ok_or_else(error)?
+            .iter()
+            .map(|item| item.as_str().ok_or_else(error))
+            .collect::<Result<_, _>>()
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+fn get_str_table(table: &Table, key: &str) -> Result<Option<BTreeMap<String, String>>, Error> {
+    let error = || Error::msg(format!("`{}` must be a table of strings", key));
+    match table.get(key) {
+        Some(value) => value
+            .as_table()
+            .ok_or_else(error)?
+            .iter()
+            .map(|(name, item)| Ok((name.clone(), item.as_str().ok_or_else(error)?.to_string())))
+            .collect::<Result<_, _>>()
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn remove_on_branch() {
+        let cfg = Config {
+            remove_on_branches: vec!["main".to_string(), "release/*".to_string()],
+            keep_on_branches: vec!["internal/*".to_string(), "release/preview-*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(cfg.remove_on_branch("main"), Some(true));
+        assert_eq!(cfg.remove_on_branch("release/1.2"), Some(true));
+        assert_eq!(cfg.remove_on_branch("release/preview-3"), Some(true));
+        assert_eq!(cfg.remove_on_branch("internal/roadmap"), Some(false));
+        assert_eq!(cfg.remove_on_branch("feature/login"), None);
+        assert_eq!(cfg.remove_on_branch("maintenance"), None);
+        assert_eq!(Config::default().remove_on_branch("main"), None);
+    }
+
+    #[test]
+    fn validate_style_disabled_with_notice() {
+        let cfg = Config {
+            style: false,
+            notice: "INTERNAL".to_string(),
+            ..Default::default()
+        };
+        assert!(cfg.validate().is_ok());
+
+        let strict_cfg = Config {
+            strict: true,
+            ..cfg
+        };
+        let err = strict_cfg.validate().unwrap_err();
+        assert!(err.to_string().contains("`notice` has no effect"));
+    }
+
+    fn table(toml: &str) -> Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn deny_unknown_keys_reports_nearest_key() {
+        let typo = table("remvoe = true\ndeny-unknown-keys = true\n");
+        let err = Config::parse(Some(&typo)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown private preprocessor configuration: unknown key `remvoe` (did you mean `remove`?)"
+        );
+
+        let lenient = table("remvoe = true\n");
+        assert_eq!(Config::parse(Some(&lenient)).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn deny_unknown_keys_accepts_clean_config() {
+        let clean = table(
+            "command = \"mdbook-private\"\nrenderers = [\"html\"]\n\
+             remove = true\ndeny-unknown-keys = true\n",
+        );
+        let cfg = Config::parse(Some(&clean)).unwrap();
+        assert!(cfg.remove);
+    }
+
+    #[test]
+    fn wildcard_and_exact_matches() {
+        assert!(matches_wildcard("html", "html"));
+        assert!(!matches_wildcard("html", "html5"));
+        assert!(matches_wildcard("html*", "html"));
+        assert!(matches_wildcard("html*", "html-print"));
+        assert!(!matches_wildcard("html*", "xhtml"));
+        assert!(matches_wildcard("*-print", "html-print"));
+        assert!(matches_wildcard("*", "confluence"));
+    }
+
+    #[test]
+    fn chapter_prefix_regex_is_checked() {
+        let valid = table("chapter-prefix = \"^(draft|wip)-\"\nchapter-prefix-regex = true\n");
+        assert!(Config::parse(Some(&valid)).unwrap().chapter_prefix_regex);
+
+        let invalid = table("chapter-prefix = \"^(draft|wip-\"\nchapter-prefix-regex = true\n");
+        let err = Config::parse(Some(&invalid)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid `chapter-prefix` regex '^(draft|wip-'"
+        );
+
+        // Without the option the value is a plain prefix
+        let plain = table("chapter-prefix = \"^(draft|wip-\"\n");
+        assert!(Config::parse(Some(&plain)).is_ok());
+    }
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("remvoe", "remove"), 2);
+        assert_eq!(edit_distance("stamp", "stamp"), 0);
+        assert_eq!(edit_distance("", "style"), 5);
+    }
+
+    #[test]
+    fn threshold_follows_levels() {
+        let cfg = Config {
+            levels: vec!["public".into(), "partner".into(), "internal".into()],
+            threshold: Some("partner".into()),
+            ..Default::default()
+        };
+        assert!(cfg.within_threshold(Some("public")));
+        assert!(cfg.within_threshold(Some("partner")));
+        assert!(!cfg.within_threshold(Some("internal")));
+        assert!(!cfg.within_threshold(Some("unknown")));
+        assert!(!cfg.within_threshold(None));
+        assert!(!Config::default().within_threshold(Some("public")));
+
+        let unknown = Config {
+            threshold: Some("secret".into()),
+            strict: true,
+            ..cfg
+        };
+        let err = unknown.validate().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`threshold` 'secret' is not one of `levels`"));
+    }
+
+    #[test]
+    fn from_toml_value_defaults() {
+        assert_eq!(
+            Config::from_toml_value(&Table::new()).unwrap(),
+            Config::default()
+        );
+    }
+
+    #[test]
+    fn from_toml_value_reads_every_option() {
+        let all = table(
+            r#"
+            enabled = false
+            config-path = "privacy.toml"
+            ignore-file = ".privateignore"
+            remove = true
+            remove-preserve-lines = true
+            remove-on-branches = ["main", "release/*"]
+            keep-on-branches = ["internal/*"]
+            always-remove = ["AKIA[0-9A-Z]{16}"]
+            review-keyword = "todo"
+            style = true
+            style-variant = "watermark"
+            verbatim = false
+            format = "endnotes"
+            css-class = true
+            data-attributes = true
+            notice = "INTERNAL"
+            box-when-no-notice = false
+            nested-notice = "innermost"
+            notice-flow = "block"
+            first-line-title = true
+            first-line-title-prefix = "Summary:"
+            notice-href = "https://example.com/policy"
+            renderer-notices = { pdf = "PARTNER CONFIDENTIAL" }
+            content-style = "border: 1px solid red"
+            notice-style = "color: red"
+            notice-styles = ["color: red", "color: blue"]
+            notice-styles-by = "hash"
+            chapter-prefix = "draft-"
+            chapter-prefixes = { "~" = "draft", "." = "ignore" }
+            chapter-prefix-enabled = false
+            chapter-prefix-regex = true
+            blocks-enabled = false
+            block-requires-own-line = true
+            case-insensitive-marker = true
+            consume-trailing-newline = false
+            normalize-unicode = true
+            removed-stub = true
+            stub-text = "Redacted"
+            stub-style = "opacity: 0.5"
+            stub-template = "{name} moved to {path}"
+            toc-omitted-note = " ({count} omitted)"
+            empty-part-policy = "note"
+            empty-part-note = "{title} (internal)"
+            chapter-banner = "Internal chapter"
+            whole-page-private = true
+            landing-remove = true
+            landing-path = "intro.md"
+            group-private-chapters = true
+            group-private-title = "Staff only"
+            page-count-banner = true
+            query-toggle = "internal"
+            no-print-page = true
+            gate = "cookie"
+            gate-cookie = "staff"
+            anchors = true
+            global-numbering = true
+            source-comments = true
+            markdown-safe = true
+            keep-empty = true
+            scan-link-text = true
+            preserve-leading-whitespace = true
+            post-process = ["collapse-blank-lines", "trim-end"]
+            block-transforms = ["mask-digits", "mask-emails"]
+            normalize-block-whitespace = true
+            line-ending = "crlf"
+            strict = true
+            deny-unknown-keys = true
+            stamp = true
+            stamp-date = "2024-01-31"
+            removed-digest = true
+            drop-empty-after-removal = true
+            dry-run = true
+            preview-path = "preview.md"
+            remove-archive = "archive.md"
+            require-matches = true
+            orphan-check = "error"
+            max-depth = 3
+            max-blocks = 500
+            levels = ["public", "internal"]
+            threshold = "public"
+            warn-unused-levels = true
+            require-declared-labels = true
+            renderers-allow = ["html*"]
+            renderers-deny = ["html-print"]
+            metadata-renderers = ["json-*"]
+            "#,
+        );
+        let expected = Config {
+            enabled: false,
+            config_path: Some(PathBuf::from("privacy.toml")),
+            ignore_file: Some(PathBuf::from(".privateignore")),
+            remove: true,
+            remove_preserve_lines: true,
+            remove_on_branches: vec!["main".to_string(), "release/*".to_string()],
+            keep_on_branches: vec!["internal/*".to_string()],
+            always_remove: vec!["AKIA[0-9A-Z]{16}".to_string()],
+            review_keyword: Some("todo".to_string()),
+            style: true,
+            style_variant: StyleVariant::Watermark,
+            verbatim: false,
+            format: Format::Endnotes,
+            css_class: true,
+            data_attributes: true,
+            notice: "INTERNAL".to_string(),
+            box_when_no_notice: false,
+            nested_notice: NestedNotice::Innermost,
+            notice_flow: NoticeFlow::Block,
+            first_line_title: true,
+            first_line_title_prefix: "Summary:".to_string(),
+            notice_href: Some("https://example.com/policy".to_string()),
+            renderer_notices: BTreeMap::from([(
+                "pdf".to_string(),
+                "PARTNER CONFIDENTIAL".to_string(),
+            )]),
+            content_style: Some("border: 1px solid red".to_string()),
+            notice_style: Some("color: red".to_string()),
+            notice_styles: vec!["color: red".to_string(), "color: blue".to_string()],
+            notice_styles_by: NoticeStylesBy::Hash,
+            chapter_prefix: "draft-".to_string(),
+            chapter_prefixes: BTreeMap::from([
+                ("~".to_string(), PrefixAction::Draft),
+                (".".to_string(), PrefixAction::Ignore),
+            ]),
+            chapter_prefix_enabled: false,
+            chapter_prefix_regex: true,
+            blocks_enabled: false,
+            regions_only: false,
+            block_requires_own_line: true,
+            case_insensitive_marker: true,
+            consume_trailing_newline: false,
+            normalize_unicode: true,
+            removed_stub: true,
+            stub_text: "Redacted".to_string(),
+            stub_style: Some("opacity: 0.5".to_string()),
+            stub_template: Some("{name} moved to {path}".to_string()),
+            toc_omitted_note: Some(" ({count} omitted)".to_string()),
+            empty_part_policy: EmptyPartPolicy::Note,
+            empty_part_note: "{title} (internal)".to_string(),
+            chapter_banner: Some("Internal chapter".to_string()),
+            classification: None,
+            whole_page_private: true,
+            landing_remove: true,
+            landing_path: Some(PathBuf::from("intro.md")),
+            group_private_chapters: true,
+            group_private_title: "Staff only".to_string(),
+            page_count_banner: true,
+            query_toggle: Some("internal".to_string()),
+            no_print_page: true,
+            preview_mode: None,
+            gate: Some(Gate::Cookie),
+            gate_cookie: "staff".to_string(),
+            anchors: true,
+            global_numbering: true,
+            source_comments: true,
+            markdown_safe: true,
+            keep_empty: true,
+            scan_link_text: true,
+            preserve_leading_whitespace: true,
+            post_processors: vec![PostProcessor::CollapseBlankLines, PostProcessor::TrimEnd],
+            block_transforms: vec![BlockTransform::MaskDigits, BlockTransform::MaskEmails],
+            normalize_block_whitespace: true,
+            line_ending: LineEnding::Crlf,
+            strict: true,
+            deny_unknown_keys: true,
+            stamp: true,
+            stamp_date: Some("2024-01-31".to_string()),
+            removed_digest: true,
+            drop_empty_after_removal: true,
+            dry_run: true,
+            preview_path: Some(PathBuf::from("preview.md")),
+            audit_path: None,
+            remove_archive: Some(PathBuf::from("archive.md")),
+            require_matches: true,
+            orphan_check: Some(OrphanCheck::Error),
+            max_depth: Some(3),
+            max_blocks: Some(500),
+            renderers_allow: vec!["html*".to_string()],
+            levels: vec!["public".to_string(), "internal".to_string()],
+            threshold: Some("public".to_string()),
+            warn_unused_levels: true,
+            require_declared_labels: true,
+            renderers_deny: vec!["html-print".to_string()],
+            metadata_renderers: vec!["json-*".to_string()],
+            language: None,
+            renderer: None,
+            ignored_chapters: Vec::new(),
+        };
+        assert_eq!(Config::from_toml_value(&all).unwrap(), expected);
+    }
+
+    #[test]
+    fn from_toml_value_errors() {
+        let cases = [
+            ("remove = \"yes\"", "`remove` must be a boolean"),
+            ("notice = 1", "`notice` must be a string"),
+            ("max-depth = -1", "`max-depth` must be a positive integer"),
+            ("levels = [1]", "`levels` must be an array of strings"),
+            (
+                "chapter-prefixes = { \"~\" = \"hide\" }",
+                "Unknown `chapter-prefixes` action 'hide'",
+            ),
+            (
+                "renderer-notices = { pdf = 1 }",
+                "`renderer-notices` must be a table of strings",
+            ),
+            (
+                "renderer-notices = \"pdf\"",
+                "`renderer-notices` must be a table of strings",
+            ),
+            (
+                "always-remove = [\"(secret\"]",
+                "Invalid `always-remove` regex '(secret'",
+            ),
+            ("format = \"footnotes\"", "Unknown `format` 'footnotes'"),
+            (
+                "review-keyword = \"re view\"",
+                "Invalid `review-keyword` 're view'",
+            ),
+            (
+                "review-keyword = \"private\"",
+                "`review-keyword` 'private' clashes with the private and public markers",
+            ),
+            ("preview-mode = \"split\"", "Unknown `preview-mode` 'split'"),
+            (
+                "empty-part-policy = \"hide\"",
+                "Unknown `empty-part-policy` 'hide'",
+            ),
+            (
+                "nested-notice = \"outermost\"",
+                "Unknown `nested-notice` 'outermost'",
+            ),
+            (
+                "post-process = [\"squash\"]",
+                "Unknown post-processor 'squash'",
+            ),
+            (
+                "strict = true\nstyle = false\nnotice = \"INTERNAL\"",
+                "Invalid private preprocessor configuration: \
+                 `notice` has no effect when `style = false`",
+            ),
+            (
+                "strict = true\nverbatim = true",
+                "Invalid private preprocessor configuration: \
+                 `verbatim` has no effect unless `style = false`",
+            ),
+            (
+                "strict = true\nnotic = \"INTERNAL\"",
+                "Unknown private preprocessor configuration: \
+                 unknown key `notic` (did you mean `notice`?)",
+            ),
+        ];
+        for (toml, message) in cases {
+            let err = Config::from_toml_value(&table(toml)).unwrap_err();
+            assert_eq!(err.to_string(), message, "for {:?}", toml);
+        }
+    }
+
+    #[test]
+    fn validate_default_config() {
+        let cfg = Config {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(cfg.validate().is_ok());
+    }
+
+    fn write_policy(name: &str, contents: &str) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(name), contents).unwrap();
+        dir
+    }
+
+    #[test]
+    fn config_path_merged_under_table() {
+        let root = write_policy(
+            "policy.toml",
+            "remove = true\nnotice = \"POLICY\"\nchapter-prefix = \"internal_\"\n",
+        );
+        let table: Table = toml::from_str(
+            r#"
+            config-path = "policy.toml"
+            notice = "BOOK"
+            "#,
+        )
+        .unwrap();
+
+        let merged = merge_config_file(&table, root.path()).unwrap();
+        let cfg = Config::from_toml_value(&merged).unwrap();
+        assert!(cfg.remove);
+        assert_eq!(cfg.notice, "BOOK");
+        assert_eq!(cfg.chapter_prefix, "internal_");
+        assert_eq!(cfg.config_path, Some(PathBuf::from("policy.toml")));
+    }
+
+    #[test]
+    fn config_path_json() {
+        let root = write_policy(
+            "policy.json",
+            r#"{ "remove": true, "levels": ["public", "internal"], "threshold": "public" }"#,
+        );
+        let table: Table = toml::from_str(
+            r#"
+            config-path = "policy.json"
+            remove = false
+            "#,
+        )
+        .unwrap();
+
+        let cfg =
+            Config::from_toml_value(&merge_config_file(&table, root.path()).unwrap()).unwrap();
+        assert!(!cfg.remove);
+        assert_eq!(cfg.levels, vec!["public", "internal"]);
+        assert_eq!(cfg.threshold.as_deref(), Some("public"));
+    }
+
+    #[test]
+    fn config_path_errors() {
+        let root = write_policy("invalid.toml", "remove = \n");
+        for path in ["missing.toml", "invalid.toml"] {
+            let mut table = Table::new();
+            table.insert("config-path".to_string(), path.into());
+            let err = merge_config_file(&table, root.path()).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Unable to read `config-path` {}",
+                    root.path().join(path).display()
+                )
+            );
+        }
+
+        let table = Table::new();
+        assert!(matches!(
+            merge_config_file(&table, root.path()).unwrap(),
+            Cow::Borrowed(_)
+        ));
+    }
+}
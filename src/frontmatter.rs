@@ -0,0 +1,170 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Prefix of the frontmatter keys read by this preprocessor
+const KEY_PREFIX: &str = "private-";
+
+/// Take the `private-*` keys out of a chapter's frontmatter
+///
+/// Frontmatter is a block of `key: value` lines between `---` lines at the very start of the
+/// chapter. Keys are returned without their prefix. Other keys are left for other tools, and the
+/// block is dropped once nothing else remains in it.
+pub(crate) fn extract(content: &str) -> (HashMap<String, String>, Cow<'_, str>) {
+    let mut keys = HashMap::new();
+    let Some((block, rest)) = split(content) else {
+        return (keys, Cow::Borrowed(content));
+    };
+
+    let mut kept = Vec::new();
+    for line in block.lines() {
+        let entry = line
+            .split_once(':')
+            .and_then(|(key, value)| Some((key.trim().strip_prefix(KEY_PREFIX)?, value)));
+        match entry {
+            Some((key, value)) => {
+                keys.insert(key.to_string(), unquote(value.trim()).to_string());
+            }
+            None => kept.push(line),
+        }
+    }
+
+    if keys.is_empty() {
+        return (keys, Cow::Borrowed(content));
+    }
+    if kept.iter().all(|line| line.trim().is_empty()) {
+        return (keys, Cow::Owned(rest.to_string()));
+    }
+
+    let newline = if content.starts_with("---\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut stripped = format!("---{}", newline);
+    for line in kept {
+        stripped.push_str(line);
+        stripped.push_str(newline);
+    }
+    stripped.push_str("---");
+    stripped.push_str(newline);
+    stripped.push_str(rest);
+    (keys, Cow::Owned(stripped))
+}
+
+/// Set a key in the chapter's frontmatter, if it has any
+///
+/// An existing line for the key is replaced, otherwise the key goes at the end of the block.
+/// Chapters without frontmatter are left alone, as mdbook would render a new block as a rule
+/// followed by a heading.
+pub(crate) fn set(content: &str, key: &str, value: &str) -> Option<String> {
+    let newline = if content.starts_with("---\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let entry = format!("{}: {}{}", key, value, newline);
+    let (block, rest) = split(content)?;
+
+    let mut updated = format!("---{}", newline);
+    let mut found = false;
+    for line in block.split_inclusive('\n') {
+        let is_key = line
+            .split_once(':')
+            .is_some_and(|(name, _)| name.trim() == key);
+        if is_key && !found {
+            updated.push_str(&entry);
+            found = true;
+        } else if !is_key {
+            updated.push_str(line);
+        }
+    }
+    if !found {
+        updated.push_str(&entry);
+    }
+    updated.push_str("---");
+    updated.push_str(newline);
+    updated.push_str(rest);
+    Some(updated)
+}
+
+/// Split leading frontmatter into its lines and the content following it
+fn split(content: &str) -> Option<(&str, &str)> {
+    let body = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&body[..offset], &body[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn extract_private_keys() {
+        let (keys, content) = extract("---\nprivate-notice: \"RESTRICTED\"\n---\n# Title\n");
+        assert_eq!(keys["notice"], "RESTRICTED");
+        assert_eq!(content, "# Title\n");
+    }
+
+    #[test]
+    fn extract_keeps_other_keys() {
+        let (keys, content) =
+            extract("---\ntitle: Roadmap\nprivate-notice: 'RESTRICTED'\n---\n# Title\n");
+        assert_eq!(keys["notice"], "RESTRICTED");
+        assert_eq!(content, "---\ntitle: Roadmap\n---\n# Title\n");
+
+        let (keys, content) = extract("---\ntitle: Roadmap\n---\n# Title\n");
+        assert!(keys.is_empty());
+        assert_eq!(content, "---\ntitle: Roadmap\n---\n# Title\n");
+    }
+
+    #[test]
+    fn set_key() {
+        assert_eq!(set("# Title\n", "classification", "internal"), None);
+        assert_eq!(
+            set(
+                "---\ntitle: Roadmap\n---\n# Title\n",
+                "classification",
+                "internal"
+            )
+            .unwrap(),
+            "---\ntitle: Roadmap\nclassification: internal\n---\n# Title\n"
+        );
+        assert_eq!(
+            set(
+                "---\r\nclassification: public\r\ntitle: Roadmap\r\n---\r\n# Title\r\n",
+                "classification",
+                "internal"
+            )
+            .unwrap(),
+            "---\r\nclassification: internal\r\ntitle: Roadmap\r\n---\r\n# Title\r\n"
+        );
+    }
+
+    #[test]
+    fn extract_without_frontmatter() {
+        for content in [
+            "# Title\n---\nprivate-notice: X\n---\n",
+            "---\nprivate-notice: X\n",
+        ] {
+            let (keys, result) = extract(content);
+            assert!(keys.is_empty());
+            assert_eq!(result, content);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::Path;
+
+use mdbook::errors::Error;
+
+use crate::config::matches_wildcard;
+
+/// Read the patterns of an ignore file
+pub(crate) fn read(path: &Path) -> Result<Vec<String>, Error> {
+    let text = fs::read_to_string(path).map_err(|e| {
+        Error::new(e).context(format!("Unable to read `ignore-file` {}", path.display()))
+    })?;
+    Ok(parse(&text))
+}
+
+/// One pattern per line, skipping blank lines and `#` comments
+fn parse(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Whether a chapter's source path, relative to the book's source directory, is ignored
+///
+/// A pattern ending in `/` matches everything within that directory, one without any `/` matches
+/// the file name wherever it is, and any other pattern matches the whole path. `*` stands for any
+/// run of characters.
+pub(crate) fn is_ignored(path: &Path, patterns: &[String]) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim_start_matches('/');
+        if let Some(dir) = pattern.strip_suffix('/') {
+            matches_wildcard(&format!("{}/*", dir), &path)
+        } else if pattern.contains('/') {
+            matches_wildcard(pattern, &path)
+        } else {
+            matches_wildcard(pattern, file_name)
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_skips_comments_and_blank_lines() {
+        assert_eq!(
+            parse("# Generated reference\n\nreference/\n  api-*.md  \n"),
+            ["reference/", "api-*.md"]
+        );
+    }
+
+    #[test]
+    fn ignored_paths() {
+        let patterns = parse("reference/\napi-*.md\nguide/setup.md\n");
+        for path in [
+            "reference/index.md",
+            "reference/deep/page.md",
+            "api-users.md",
+            "guide/api-v2.md",
+            "guide/setup.md",
+        ] {
+            assert!(is_ignored(Path::new(path), &patterns), "{}", path);
+        }
+        for path in ["guide/reference.md", "setup.md", "chapter_1.md"] {
+            assert!(!is_ignored(Path::new(path), &patterns), "{}", path);
+        }
+    }
+}
@@ -5,11 +5,18 @@ use mdbook::preprocess::{CmdPreprocessor, Preprocessor};
 use mdbook_private::Private;
 use semver::{Version, VersionReq};
 use std::io;
+use std::path::Path;
 use std::process;
 
 pub fn make_app() -> Command {
     Command::new("mdbook-private")
         .about("A mdbook preprocessor which removes private sections")
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .global(true)
+                .help("Read the configuration from `[preprocessor.<name>]`, for aliased tables"),
+        )
         .subcommand(
             Command::new("supports")
                 .arg(Arg::new("renderer").required(true))
@@ -20,17 +27,36 @@ pub fn make_app() -> Command {
 fn main() {
     env_logger::try_init().unwrap();
     let matches = make_app().get_matches();
-
-    let preprocessor = Private;
+    let private = || match matches.get_one::<String>("name") {
+        Some(name) => Private::with_name(name),
+        None => Private::new(),
+    };
 
     if let Some(sub_args) = matches.subcommand_matches("supports") {
+        // A broken configuration is reported by the run itself, rather than read as unsupported
+        let preprocessor = load_preprocessor(private()).unwrap_or_else(|e| {
+            warn!("{}", e);
+            private()
+        });
         handle_supports(&preprocessor, sub_args);
-    } else if let Err(e) = handle_preprocessing(&preprocessor) {
+    } else if let Err(e) = handle_preprocessing(&private()) {
         error!("{}", e);
         process::exit(1);
     }
 }
 
+/// mdbook doesn't pass the configuration to `supports`, and runs it from its own working
+/// directory, so the renderer lists are read from a `book.toml` found there when mdbook is run
+/// from the book root. `run` applies them again with the book's configuration either way.
+fn load_preprocessor(private: Private) -> Result<Private, Error> {
+    let path = Path::new("book.toml");
+    if !path.exists() {
+        return Ok(private);
+    }
+    let config = mdbook::Config::from_disk(path)?;
+    private.with_book_config(&config)
+}
+
 fn handle_preprocessing(pre: &dyn Preprocessor) -> Result<(), Error> {
     let (ctx, book) = CmdPreprocessor::parse_input(io::stdin())?;
 
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use mdbook::book::Book;
+use mdbook::errors::Error;
+use mdbook::BookItem;
+
+use crate::{has_private_content, Config};
+
+/// Markdown files under the source directory that aren't chapters of the book, but hold private
+/// content
+///
+/// mdbook only hands over the chapters listed in `SUMMARY.md`, so the source directory is walked
+/// for the files it left out. Paths are relative to the source directory.
+pub(crate) fn find(src: &Path, book: &Book, cfg: &Config) -> Result<Vec<PathBuf>, Error> {
+    let chapters: HashSet<&Path> = book
+        .iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(chapter) => chapter.source_path.as_deref(),
+            _ => None,
+        })
+        .collect();
+
+    let mut orphans = Vec::new();
+    let mut dirs = vec![src.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| Error::new(e).context(format!("Unable to read {}", dir.display())))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(src) else {
+                continue;
+            };
+            let markdown = path.extension().is_some_and(|ext| ext == "md");
+            if !markdown || relative == Path::new("SUMMARY.md") || chapters.contains(relative) {
+                continue;
+            }
+            // Files that aren't UTF-8 can't hold markers mdbook would read
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            if has_private_content(&content, cfg) {
+                orphans.push(relative.to_path_buf());
+            }
+        }
+    }
+
+    orphans.sort();
+    Ok(orphans)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mdbook::book::Chapter;
+
+    #[test]
+    fn orphans_with_private_content() {
+        let src =
+            std::env::temp_dir().join(format!("mdbook-private-orphans-{}", std::process::id()));
+        fs::create_dir_all(src.join("guide")).unwrap();
+        fs::write(src.join("SUMMARY.md"), "- [Chapter 1](chapter_1.md)\n").unwrap();
+        fs::write(src.join("chapter_1.md"), "<!--private Secret -->\n").unwrap();
+        fs::write(src.join("notes.md"), "# Notes\n").unwrap();
+        fs::write(src.join("guide/stray.md"), "<!--private\nSecret\n-->\n").unwrap();
+        fs::write(src.join("draft.md"), "<!--private-chapter-->\n# Draft\n").unwrap();
+
+        let mut book = Book::new();
+        book.push_item(Chapter::new(
+            "Chapter 1",
+            "<!--private Secret -->\n".to_string(),
+            "chapter_1.md",
+            vec![],
+        ));
+        let orphans = find(&src, &book, &Config::default());
+        fs::remove_dir_all(&src).unwrap();
+
+        assert_eq!(
+            orphans.unwrap(),
+            [PathBuf::from("draft.md"), PathBuf::from("guide/stray.md")]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::sync::LazyLock;
+
+use mdbook::errors::Error;
+use regex::{Captures, Regex};
+
+/// A pass over the fully processed content of a chapter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostProcessor {
+    /// Collapse runs of blank lines into a single blank line
+    CollapseBlankLines,
+    /// Remove trailing whitespace at the end of the chapter, keeping a final newline
+    TrimEnd,
+    /// Write HTML tags outside code with sorted, single-quoted attributes
+    Canonicalize,
+}
+
+impl PostProcessor {
+    pub(crate) fn parse(value: &str) -> Result<PostProcessor, Error> {
+        match value {
+            "collapse-blank-lines" => Ok(PostProcessor::CollapseBlankLines),
+            "trim-end" => Ok(PostProcessor::TrimEnd),
+            "canonicalize" => Ok(PostProcessor::Canonicalize),
+            _ => Err(Error::msg(format!("Unknown post-processor '{}'", value))),
+        }
+    }
+
+    fn apply(&self, content: &str) -> String {
+        match self {
+            PostProcessor::CollapseBlankLines => collapse_blank_lines(content),
+            PostProcessor::TrimEnd => trim_end(content),
+            PostProcessor::Canonicalize => canonicalize(content),
+        }
+    }
+}
+
+/// Run the post-processors over the content, in order
+pub(crate) fn run(content: String, post_processors: &[PostProcessor]) -> String {
+    post_processors
+        .iter()
+        .fold(content, |content, stage| stage.apply(&content))
+}
+
+fn collapse_blank_lines(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut blank_run = 0;
+
+    for line in content.split_inclusive('\n') {
+        if line.trim().is_empty() && line.ends_with('\n') {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        result.push_str(line);
+    }
+
+    result
+}
+
+fn trim_end(content: &str) -> String {
+    let trimmed = content.trim_end();
+    if trimmed.len() < content.len() && content.ends_with('\n') {
+        format!("{}\n", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"(?P<comment><!--(?s:.*?)-->)|(?P<code>``(?s:.*?)``|`[^`]*`)|<(?P<name>[a-zA-Z][a-zA-Z0-9-]*)"#,
+        r#"(?P<attributes>(?:\s+[^\s"'<>/=]+(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s"'<>=`]+))?)*)"#,
+        r#"\s*(?P<close>/?)>"#
+    ))
+    .unwrap()
+});
+
+static ATTRIBUTE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?P<key>[^\s"'<>/=]+)(?:\s*=\s*(?:"(?P<double>[^"]*)"|'(?P<single>[^']*)'|(?P<bare>[^\s"'<>=`]+)))?"#,
+    )
+    .unwrap()
+});
+
+/// Rewrite the HTML tags of the content in a single form, leaving code blocks and spans alone
+///
+/// Attributes are sorted by name, separated by single spaces and quoted with single quotes, so
+/// the output can be compared byte for byte whatever wrote the markup.
+fn canonicalize(content: &str) -> String {
+    map_prose(content, canonical_tags)
+}
+
+/// Apply `map` to the runs of lines outside fenced and indented code blocks, leaving the code
+/// blocks as written
+pub(crate) fn map_prose(content: &str, map: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut prose = String::new();
+    let mut fence: Option<&str> = None;
+    let mut indented = false;
+    let mut after_blank = true;
+
+    for line in content.split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| line.trim_start().starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => {
+                result.push_str(&map(&prose));
+                prose.clear();
+                result.push_str(line);
+                fence = Some(marker);
+                indented = false;
+            }
+            (Some(open), Some(marker)) if open == marker => {
+                result.push_str(line);
+                fence = None;
+            }
+            (Some(_), _) => result.push_str(line),
+            (None, None) => {
+                // An indented code block opens after a blank line and runs on over blank lines
+                if !blank {
+                    let code = line.starts_with("    ") || line.starts_with('\t');
+                    indented = code && (indented || after_blank);
+                }
+                if indented && !blank {
+                    result.push_str(&map(&prose));
+                    prose.clear();
+                    result.push_str(line);
+                } else {
+                    prose.push_str(line);
+                }
+            }
+        }
+        after_blank = blank;
+    }
+
+    result.push_str(&map(&prose));
+    result
+}
+
+fn canonical_tags(content: &str) -> String {
+    TAG_RE
+        .replace_all(content, |caps: &Captures| {
+            if let Some(literal) = caps.name("comment").or_else(|| caps.name("code")) {
+                return literal.as_str().to_string();
+            }
+            let mut attributes: Vec<(&str, Option<&str>)> = ATTRIBUTE_RE
+                .captures_iter(&caps["attributes"])
+                .map(|attribute| {
+                    let value = ["double", "single", "bare"]
+                        .iter()
+                        .find_map(|group| attribute.name(group))
+                        .map(|value| value.as_str());
+                    (attribute.name("key").unwrap().as_str(), value)
+                })
+                .collect();
+            attributes.sort_by_key(|(key, _)| *key);
+
+            let mut tag = format!("<{}", &caps["name"]);
+            for (key, value) in attributes {
+                match value {
+                    Some(value) => {
+                        tag.push_str(&format!(" {}='{}'", key, value.replace('\'', "&#39;")))
+                    }
+                    None => tag.push_str(&format!(" {}", key)),
+                }
+            }
+            tag.push_str(&caps["close"]);
+            tag.push('>');
+            tag
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn collapse_blank_lines_stage() {
+        let content = "# Title\n\n\n\nText\n\n  \nMore\n";
+        assert_eq!(
+            PostProcessor::CollapseBlankLines.apply(content),
+            "# Title\n\nText\n\nMore\n"
+        );
+    }
+
+    #[test]
+    fn trim_end_stage() {
+        assert_eq!(PostProcessor::TrimEnd.apply("Text  \n\n\n"), "Text\n");
+        assert_eq!(PostProcessor::TrimEnd.apply("Text  "), "Text");
+        assert_eq!(PostProcessor::TrimEnd.apply("Text\n"), "Text\n");
+    }
+
+    #[test]
+    fn pipeline_composition() {
+        let content = "Text\n\n\n\nMore\n\n\n".to_string();
+        let stages = [PostProcessor::CollapseBlankLines, PostProcessor::TrimEnd];
+        assert_eq!(run(content.clone(), &stages), "Text\n\nMore\n");
+        assert_eq!(run(content.clone(), &[]), content);
+    }
+
+    #[test]
+    fn canonicalize_stage() {
+        assert_eq!(
+            PostProcessor::Canonicalize.apply(
+                "<div  style=\"color: red;\"\n class='note' hidden><img src=a.png alt=\"It's\"/></div>\n"
+            ),
+            "<div class='note' hidden style='color: red;'><img alt='It&#39;s' src='a.png'/></div>\n"
+        );
+        // Comments, closing tags, autolinks and code are left as written
+        for content in [
+            "<!-- <b class=\"x\"> -->\n",
+            "</div>\n",
+            "<https://example.com>\n",
+            "```html\n<div style=\"a\" class=\"b\">\n```\n",
+        ] {
+            assert_eq!(PostProcessor::Canonicalize.apply(content), content);
+        }
+    }
+
+    #[test]
+    fn canonicalize_skips_code_spans() {
+        for content in [
+            "Write `<a href=\"x\" class=\"y\">` for links\n",
+            "Or ``<a href=\"x\" class=\"y\">` `` with a backtick\n",
+        ] {
+            assert_eq!(PostProcessor::Canonicalize.apply(content), content);
+        }
+        assert_eq!(
+            PostProcessor::Canonicalize.apply("`code` <b class=\"c\" id=\"i\"> `more`\n"),
+            "`code` <b class='c' id='i'> `more`\n"
+        );
+    }
+
+    #[test]
+    fn canonicalize_skips_indented_code() {
+        let content = "Text\n\n    <a href=\"x\" class=\"y\">\n\n\t<b id=\"i\" class=\"c\">\n\n\
+                       <b id=\"i\" class=\"c\">\n";
+        assert_eq!(
+            PostProcessor::Canonicalize.apply(content),
+            "Text\n\n    <a href=\"x\" class=\"y\">\n\n\t<b id=\"i\" class=\"c\">\n\n\
+             <b class='c' id='i'>\n"
+        );
+        // Indented lines continuing a paragraph aren't code
+        assert_eq!(
+            PostProcessor::Canonicalize.apply("Text\n    <b id=\"i\" class=\"c\">\n"),
+            "Text\n    <b class='c' id='i'>\n"
+        );
+    }
+
+    #[test]
+    fn canonicalize_attribute_order_is_stable() {
+        let orders = [
+            "<span title=\"t\" class=\"c\" data-private-label=\"l\">",
+            "<span class='c' data-private-label='l' title='t'>",
+            "<span data-private-label=l  title=\"t\" class=c>",
+        ];
+        for content in orders {
+            assert_eq!(
+                PostProcessor::Canonicalize.apply(content),
+                "<span class='c' data-private-label='l' title='t'>"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_post_processor() {
+        assert_eq!(
+            PostProcessor::parse("trim-end").unwrap(),
+            PostProcessor::TrimEnd
+        );
+        assert!(PostProcessor::parse("trim").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt::Write;
+
+use mdbook::book::Book;
+use mdbook::BookItem;
+use regex::Regex;
+
+use crate::{
+    applies_to_language, block_content, block_label, chapter_blocks, is_private_chapter,
+    line_numbers, source_path, split_attributes, Config,
+};
+
+/// Longest excerpt of a private block shown in the preview, in characters
+const EXCERPT_LENGTH: usize = 40;
+
+/// List in markdown where the private blocks of the book are, without judging what is removed
+pub(crate) fn render_audit(book: &Book, cfg: &Config) -> String {
+    let mut audit = String::from("# mdbook-private audit\n");
+    for item in book.iter() {
+        let BookItem::Chapter(chapter) = item else {
+            continue;
+        };
+
+        let mut blocks = chapter_blocks(chapter, cfg).peekable();
+        if blocks.peek().is_none() {
+            continue;
+        }
+        let _ = writeln!(audit, "\n## {} ({})\n", chapter.name, source_path(chapter));
+        for caps in blocks {
+            let (attributes, body) = split_attributes(block_content(&caps).as_str());
+            let line = line_numbers(&chapter.content, &caps).0;
+            let _ = match block_label(&caps, &attributes) {
+                Some(label) => writeln!(
+                    audit,
+                    "- Line {}, label '{}': \"{}\"",
+                    line,
+                    label,
+                    excerpt(body)
+                ),
+                None => writeln!(audit, "- Line {}: \"{}\"", line, excerpt(body)),
+            };
+        }
+    }
+    audit
+}
+
+/// Describe in markdown what a `remove` build would strip from the book
+pub(crate) fn render_preview(book: &Book, cfg: &Config, prefix_re: Option<&Regex>) -> String {
+    let mut preview = String::from("# mdbook-private preview\n");
+    render_items(&book.sections, cfg, prefix_re, false, &mut preview);
+    preview
+}
+
+fn render_items(
+    items: &[BookItem],
+    cfg: &Config,
+    prefix_re: Option<&Regex>,
+    parent_dropped: bool,
+    preview: &mut String,
+) {
+    for item in items {
+        let BookItem::Chapter(chapter) = item else {
+            continue;
+        };
+
+        let path = source_path(chapter);
+        let dropped = parent_dropped || is_private_chapter(chapter, cfg, prefix_re);
+
+        let _ = writeln!(preview, "\n## {} ({})\n", chapter.name, path);
+        if dropped {
+            preview.push_str("- Chapter would be dropped\n");
+        } else {
+            let mut found = false;
+            for caps in chapter_blocks(chapter, cfg) {
+                let (attributes, body) = split_attributes(block_content(&caps).as_str());
+                let public = cfg.within_threshold(block_label(&caps, &attributes));
+                if applies_to_language(&attributes, cfg) && !public {
+                    let _ = writeln!(preview, "- Block would be removed: \"{}\"", excerpt(body));
+                    found = true;
+                }
+            }
+            if !found {
+                preview.push_str("- Nothing would be removed\n");
+            }
+        }
+
+        render_items(&chapter.sub_items, cfg, prefix_re, dropped, preview);
+    }
+}
+
+/// First line of a block, truncated so long secrets don't end up in the preview
+fn excerpt(body: &str) -> String {
+    let line = body.trim().lines().next().unwrap_or_default();
+    if line.chars().count() > EXCERPT_LENGTH {
+        line.chars().take(EXCERPT_LENGTH).collect::<String>() + "…"
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mdbook::book::Chapter;
+
+    #[test]
+    fn preview_lists_removals() {
+        let mut book = Book::new();
+        book.push_item(Chapter::new(
+            "Chapter 1",
+            "# Chapter 1\n<!--private\nThe launch date of the new product line is March 3rd\nMore\n-->\n"
+                .to_string(),
+            "chapter_1.md",
+            vec![],
+        ));
+        book.push_item(Chapter::new(
+            "Chapter 2",
+            "# Chapter 2\n".to_string(),
+            "_chapter_2.md",
+            vec![],
+        ));
+        book.push_item(Chapter::new(
+            "Chapter 3",
+            "# Chapter 3\n".to_string(),
+            "chapter_3.md",
+            vec![],
+        ));
+
+        let preview = render_preview(&book, &Config::default(), None);
+        assert_eq!(
+            preview,
+            "# mdbook-private preview\n\
+             \n## Chapter 1 (chapter_1.md)\n\n\
+             - Block would be removed: \"The launch date of the new product line …\"\n\
+             \n## Chapter 2 (_chapter_2.md)\n\n\
+             - Chapter would be dropped\n\
+             \n## Chapter 3 (chapter_3.md)\n\n\
+             - Nothing would be removed\n"
+        );
+    }
+
+    #[test]
+    fn audit_lists_blocks() {
+        let mut book = Book::new();
+        let mut chapter = Chapter::new(
+            "Chapter 1",
+            "# Chapter 1\n\n<!--private label='internal' Roadmap -->\n".to_string(),
+            "chapter_1.md",
+            vec![],
+        );
+        chapter.sub_items.push(BookItem::Chapter(Chapter::new(
+            "Section 1.1",
+            "<!--private\nLaunch date\n-->\n".to_string(),
+            "section_1_1.md",
+            vec![],
+        )));
+        book.push_item(chapter);
+        book.push_item(Chapter::new(
+            "Chapter 2",
+            "# Chapter 2\n".to_string(),
+            "chapter_2.md",
+            vec![],
+        ));
+
+        assert_eq!(
+            render_audit(&book, &Config::default()),
+            "# mdbook-private audit\n\
+             \n## Chapter 1 (chapter_1.md)\n\n\
+             - Line 3, label 'internal': \"Roadmap\"\n\
+             \n## Section 1.1 (section_1_1.md)\n\n\
+             - Line 1: \"Launch date\"\n"
+        );
+    }
+
+    #[test]
+    fn excerpt_is_truncated() {
+        assert_eq!(excerpt("short"), "short");
+        assert_eq!(excerpt(&"x".repeat(50)), "x".repeat(40) + "…");
+        assert_eq!(excerpt("\nfirst line\nsecond line"), "first line");
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::LazyLock;
+
+use mdbook::errors::Error;
+use regex::Regex;
+
+/// Character replacing masked content
+const MASK: char = '•';
+
+static EMAIL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+").unwrap());
+
+/// Markdown and HTML syntax whose digits aren't content: ordered list markers, link and image
+/// targets, reference definitions, numeric entities, heading ids and tags
+static SYNTAX_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?m)^[ \t]*(?:>[ \t]*)*\d+[.)][ \t]",
+        r"|\]\([^)\n]*\)|\]\[[^\]\n]*\]|^[ \t]*\[[^\]\n]+\]:[ \t]*\S+",
+        r"|&#(?:\d+|[xX][0-9a-fA-F]+);|\{#[^}\n]*\}|</?[a-zA-Z][^>\n]*>"
+    ))
+    .unwrap()
+});
+
+/// A change applied to the content of retained private blocks before it is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockTransform {
+    /// Replace the digits of the text with a mask character, leaving markdown and HTML syntax
+    MaskDigits,
+    /// Replace email addresses with mask characters, keeping the `@`
+    MaskEmails,
+}
+
+impl BlockTransform {
+    pub(crate) fn parse(value: &str) -> Result<BlockTransform, Error> {
+        match value {
+            "mask-digits" => Ok(BlockTransform::MaskDigits),
+            "mask-emails" => Ok(BlockTransform::MaskEmails),
+            _ => Err(Error::msg(format!("Unknown block transform '{}'", value))),
+        }
+    }
+
+    fn apply(&self, content: &str) -> String {
+        match self {
+            BlockTransform::MaskDigits => mask_digits(content),
+            BlockTransform::MaskEmails => EMAIL_RE
+                .replace_all(content, |caps: &regex::Captures| {
+                    caps[0]
+                        .chars()
+                        .map(|c| if c == '@' { c } else { MASK })
+                        .collect::<String>()
+                })
+                .into_owned(),
+        }
+    }
+}
+
+/// Mask the digits between the pieces of syntax of the content
+fn mask_digits(content: &str) -> String {
+    let mask = |text: &str| -> String {
+        text.chars()
+            .map(|c| if c.is_ascii_digit() { MASK } else { c })
+            .collect()
+    };
+
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for syntax in SYNTAX_RE.find_iter(content) {
+        result.push_str(&mask(&content[last..syntax.start()]));
+        result.push_str(syntax.as_str());
+        last = syntax.end();
+    }
+    result.push_str(&mask(&content[last..]));
+    result
+}
+
+/// Trim the end of every line, and shorten runs of three or more blank lines to a single one
+pub(crate) fn normalize_whitespace(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut blank = 0;
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !result.is_empty() {
+            let breaks = if blank >= 3 { 2 } else { blank + 1 };
+            result.push_str(&"\n".repeat(breaks));
+        }
+        blank = 0;
+        result.push_str(line);
+    }
+    result
+}
+
+/// Run the transforms over the content of a block, in order
+pub(crate) fn run(content: &str, transforms: &[BlockTransform]) -> String {
+    transforms
+        .iter()
+        .fold(content.to_string(), |content, transform| {
+            transform.apply(&content)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_block_whitespace() {
+        assert_eq!(
+            normalize_whitespace("First  \nSecond\t\n\n\n\n\nThird\n  \n\nFourth\n\nFifth  "),
+            "First\nSecond\n\nThird\n\n\nFourth\n\nFifth"
+        );
+        assert_eq!(normalize_whitespace("\r\n Indented \r\n"), " Indented");
+    }
+
+    #[test]
+    fn mask_digits() {
+        assert_eq!(
+            BlockTransform::MaskDigits.apply("Call 555-0123 before 9am"),
+            "Call •••-•••• before •am"
+        );
+    }
+
+    #[test]
+    fn mask_digits_leaves_syntax() {
+        let content = "1. Budget 2024 ![fig1](img/fig1.png)\n\
+                       2) See [Q3 plan](plans/q3.md#step-2) &#8212; 40k\n\
+                       > 3. Quoted 7\n\
+                       ## Phase 2 {#phase-2}\n\
+                       <span data-id=\"42\">99</span> [ref][q4]\n\
+                       [q4]: plans/q4.md\n";
+        assert_eq!(
+            BlockTransform::MaskDigits.apply(content),
+            "1. Budget •••• ![fig•](img/fig1.png)\n\
+             2) See [Q• plan](plans/q3.md#step-2) &#8212; ••k\n\
+             > 3. Quoted •\n\
+             ## Phase • {#phase-2}\n\
+             <span data-id=\"42\">••</span> [ref][q4]\n\
+             [q4]: plans/q4.md\n"
+        );
+    }
+
+    #[test]
+    fn mask_emails() {
+        assert_eq!(
+            BlockTransform::MaskEmails.apply("Ask jane.doe@example.com, not @team."),
+            "Ask ••••••••@•••••••••••, not @team."
+        );
+    }
+
+    #[test]
+    fn parse_block_transform() {
+        assert_eq!(
+            BlockTransform::parse("mask-emails").unwrap(),
+            BlockTransform::MaskEmails
+        );
+        assert!(BlockTransform::parse("mask").is_err());
+    }
+}